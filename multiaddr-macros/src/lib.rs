@@ -0,0 +1,260 @@
+//! Multiaddress literals that are parsed at compile time.
+//!
+//! See [`ma!`].
+
+use multiaddr::{Error, Multiaddr, ProtocolKind, ValueSize};
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{quote, quote_spanned};
+use std::{fmt, ops::Range};
+use syn::{
+    parse::{Parse, ParseStream},
+    spanned::Spanned,
+    Expr, Ident, LitStr, Token,
+};
+
+/// Returns the `Protocol` variant of the protocol with the given name, if its value can be
+/// interpolated.
+///
+/// The variants are named like the [`ProtocolKind`]s, apart from the WebSockets with a path.
+fn variant(name: &str) -> Option<String> {
+    match name.parse::<ProtocolKind>().ok()? {
+        // The value of `/onion` consists of two fields.
+        ProtocolKind::Onion => None,
+        ProtocolKind::XParityWs => Some("Ws".into()),
+        ProtocolKind::XParityWss => Some("Wss".into()),
+        kind if kind.value_size() == ValueSize::None => None,
+        kind => Some(format!("{kind:?}")),
+    }
+}
+
+/// Creates a [`Multiaddr`](https://docs.rs/multiaddr/latest/multiaddr/struct.Multiaddr.html)
+/// from a string literal that is parsed at compile time.
+///
+/// The address is embedded in its binary representation, so no string is parsed at runtime.
+/// The value of a component can be left open with a `{name}` slot, which takes the variable
+/// `name`, or a `{}` slot, which takes the next argument after the literal. The value is
+/// turned into the matching `Protocol` variant with the `Into` trait, like in
+/// `multiaddr!`. A slot must be the whole value of a component.
+///
+/// # Example
+///
+/// ```
+/// use multiaddr::Multiaddr;
+/// use multiaddr_macros::ma;
+/// use std::net::Ipv4Addr;
+///
+/// let addr = ma!("/ip4/127.0.0.1/tcp/80/ws");
+/// assert_eq!(addr, "/ip4/127.0.0.1/tcp/80/ws".parse::<Multiaddr>().unwrap());
+///
+/// let port = 4001u16;
+/// let addr = ma!("/ip4/{}/tcp/{port}/ws", Ipv4Addr::LOCALHOST);
+/// assert_eq!(addr.to_string(), "/ip4/127.0.0.1/tcp/4001/ws");
+/// ```
+///
+/// Invalid components are reported at compile time:
+///
+/// ```compile_fail
+/// # use multiaddr_macros::ma;
+/// let addr = ma!("/ip4/127.0.0.1/tcp/80x");
+/// ```
+///
+/// ```compile_fail
+/// # use multiaddr_macros::ma;
+/// let addr = ma!("/ip4/127.0.0.1/tpc/80");
+/// ```
+///
+/// ```compile_fail
+/// # use multiaddr_macros::ma;
+/// let port = "80";
+/// let addr = ma!("/ip4/127.0.0.1/tcp/{port}");
+/// ```
+///
+#[proc_macro]
+pub fn ma(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as Input);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+struct Input {
+    template: LitStr,
+    args: Vec<Expr>,
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let template = input.parse()?;
+        let mut args = Vec::new();
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            args.push(input.parse()?);
+        }
+        Ok(Input { template, args })
+    }
+}
+
+/// A part of the expanded multiaddress.
+enum Piece {
+    /// Components known at compile time, in their binary representation.
+    Encoded(Vec<u8>),
+    /// A component whose value is interpolated.
+    Slot { variant: Ident, value: TokenStream2 },
+}
+
+fn expand(input: Input) -> syn::Result<TokenStream2> {
+    let lit = &input.template;
+    let template = lit.value();
+    let mut args = input.args.into_iter();
+    let mut pieces = Vec::new();
+    // The number of components and the byte offset of the text parsed so far.
+    let mut index = 0;
+    let mut start = 0;
+
+    while let Some(open) = template[start..].find('{').map(|i| start + i) {
+        let close = template[open..]
+            .find('}')
+            .map(|i| open + i)
+            .ok_or_else(|| error(lit, open..template.len(), "unclosed value slot"))?;
+        let slot = open..close + 1;
+        let (prefix, name) = template[start..open]
+            .strip_suffix('/')
+            .and_then(|text| text.rsplit_once('/'))
+            .ok_or_else(|| error(lit, slot.clone(), "value slot must follow `/<protocol>/`"))?;
+        if !template[close + 1..].is_empty() && !template[close + 1..].starts_with('/') {
+            return Err(error(
+                lit,
+                slot,
+                "value slot must be the whole value of a component",
+            ));
+        }
+        let Some(variant) = variant(name) else {
+            let message = format!("cannot interpolate the value of `/{name}`");
+            return Err(error(lit, slot, message));
+        };
+
+        index += encode(
+            lit,
+            &template,
+            start..start + prefix.len(),
+            index,
+            &mut pieces,
+        )?;
+
+        let value = match template[open + 1..close].trim() {
+            "" => {
+                let arg = args
+                    .next()
+                    .ok_or_else(|| error(lit, slot, "missing argument for value slot"))?;
+                quote_spanned!(arg.span()=> ::core::convert::Into::into(#arg))
+            }
+            name => {
+                let ident = syn::parse_str::<Ident>(name)
+                    .map_err(|_| error(lit, slot, "value slot must be `{}` or `{<variable>}`"))?;
+                let ident = Ident::new(&ident.to_string(), lit.span());
+                quote!(::core::convert::Into::into(#ident))
+            }
+        };
+        pieces.push(Piece::Slot {
+            variant: Ident::new(&variant, Span::call_site()),
+            value,
+        });
+        index += 1;
+        start = close + 1;
+    }
+    encode(lit, &template, start..template.len(), index, &mut pieces)?;
+
+    if let Some(arg) = args.next() {
+        return Err(syn::Error::new_spanned(
+            arg,
+            "argument never used in multiaddr",
+        ));
+    }
+
+    Ok(match pieces.as_slice() {
+        [] => quote!(::multiaddr::Multiaddr::empty()),
+        [Piece::Encoded(bytes)] => quote!(::multiaddr::Multiaddr::__from_static(&[#(#bytes),*])),
+        pieces => {
+            // Not visible to the interpolated expressions, which may use the same name.
+            let addr = Ident::new("addr", Span::mixed_site());
+            let capacity = pieces
+                .iter()
+                .map(|p| match p {
+                    Piece::Encoded(bytes) => bytes.len(),
+                    Piece::Slot { .. } => 0,
+                })
+                .sum::<usize>();
+            let steps = pieces.iter().map(|p| match p {
+                Piece::Encoded(bytes) => quote!(#addr.__push_encoded(&[#(#bytes),*]);),
+                Piece::Slot { variant, value } => {
+                    quote!(#addr.push(::multiaddr::Protocol::#variant(#value));)
+                }
+            });
+            quote!({
+                let mut #addr = ::multiaddr::Multiaddr::with_capacity(#capacity);
+                #(#steps)*
+                #addr
+            })
+        }
+    })
+}
+
+/// Parses the components in `range` of the template, which follow `index` components, and
+/// returns their number.
+fn encode(
+    lit: &LitStr,
+    template: &str,
+    range: Range<usize>,
+    index: usize,
+    pieces: &mut Vec<Piece>,
+) -> syn::Result<usize> {
+    let text = &template[range.clone()];
+    if text.is_empty() {
+        return Ok(0);
+    }
+    let addr = text.parse::<Multiaddr>().map_err(|e| match e {
+        Error::InvalidComponentString {
+            index: i,
+            offset,
+            protocol,
+            value,
+            error,
+        } => {
+            let start = range.start
+                + text
+                    .char_indices()
+                    .nth(offset)
+                    .map_or(text.len(), |(b, _)| b);
+            let len = value.as_deref().or(protocol).map_or(0, str::len);
+            let e = Error::InvalidComponentString {
+                index: index + i,
+                offset: template[..start].chars().count(),
+                protocol,
+                value,
+                error,
+            };
+            self::error(lit, start..start + len, e)
+        }
+        e => self::error(lit, range, e),
+    })?;
+    pieces.push(Piece::Encoded(addr.to_vec()));
+    Ok(addr.iter().count())
+}
+
+/// Returns an error pointing at the bytes in `range` of the literal, where supported, or
+/// else at the whole literal.
+fn error(lit: &LitStr, range: Range<usize>, message: impl fmt::Display) -> syn::Error {
+    let token = lit.token();
+    let source = token.to_string();
+    // Offsets only carry over to the source of literals without escapes.
+    let plain = source.strip_prefix('"').and_then(|s| s.strip_suffix('"')) == Some(&lit.value());
+    let span = plain
+        .then(|| token.subspan(range.start + 1..range.end + 1))
+        .flatten()
+        .unwrap_or_else(|| lit.span());
+    syn::Error::new(span, message)
+}
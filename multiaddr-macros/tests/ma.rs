@@ -0,0 +1,72 @@
+use multiaddr::{Multiaddr, PeerId, Protocol};
+use multiaddr_macros::ma;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+#[test]
+fn literal() {
+    for (addr, s) in [
+        (ma!(""), ""),
+        (ma!("/ip4/1.2.3.4/tcp/80"), "/ip4/1.2.3.4/tcp/80"),
+        (
+            ma!("/dns/example.com/tcp/443/tls/sni/example.com/http-path/foo%2Fbar"),
+            "/dns/example.com/tcp/443/tls/sni/example.com/http-path/foo%2Fbar",
+        ),
+        (
+            ma!("/ip6/::1/udp/4001/quic-v1/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC"),
+            "/ip6/::1/udp/4001/quic-v1/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC",
+        ),
+        (
+            ma!("/ipfs/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC"),
+            "/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC",
+        ),
+    ] {
+        assert_eq!(addr, s.parse::<Multiaddr>().unwrap());
+        assert_eq!(addr.to_string(), s);
+    }
+}
+
+#[test]
+fn interpolation() {
+    let port = 8080u16;
+    let addr = [127, 0, 0, 1];
+    assert_eq!(
+        ma!("/ip4/{addr}/tcp/{port}").to_string(),
+        "/ip4/127.0.0.1/tcp/8080"
+    );
+    assert_eq!(
+        ma!("/ip6/{}/udp/{}/quic-v1", Ipv6Addr::LOCALHOST, port + 1).to_string(),
+        "/ip6/::1/udp/8081/quic-v1"
+    );
+
+    let host = String::from("example.com");
+    let peer: PeerId = "QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC"
+        .parse()
+        .unwrap();
+    let addr = ma!(
+        "/dns/{}/tcp/443/tls/sni/{host}/ws/p2p/{peer}",
+        host.as_str()
+    );
+    assert_eq!(
+        addr.iter().collect::<Vec<_>>(),
+        vec![
+            Protocol::Dns("example.com".into()),
+            Protocol::Tcp(443),
+            Protocol::Tls,
+            Protocol::Sni("example.com".into()),
+            Protocol::Ws("/".into()),
+            Protocol::P2p(peer),
+        ]
+    );
+
+    let ip = Ipv4Addr::new(10, 0, 0, 1);
+    assert_eq!(ma!("/ip4/{ip}").to_string(), "/ip4/10.0.0.1");
+    assert_eq!(ma!("/memory/{}", 42u64,).to_string(), "/memory/42");
+    assert_eq!(
+        ma!("/unix/{}", "/tmp/node.sock").to_string(),
+        "/unix/%2Ftmp%2Fnode.sock"
+    );
+    assert_eq!(
+        ma!("/ip4/{ip}/tcp/80/x-parity-ws/{}", "/chat"),
+        ma!("/ip4/10.0.0.1/tcp/80/x-parity-ws/%2Fchat")
+    );
+}
@@ -0,0 +1,136 @@
+//! Parsing and generation of `dnsaddr` TXT records.
+//!
+//! A `/dnsaddr/<name>` component is resolved from the TXT records of `_dnsaddr.<name>`, each
+//! of the form `dnsaddr=<multiaddr>`. See [`MultiaddrSlice::resolve`] for resolving them.
+//!
+//! # Example
+//!
+//! ```
+//! use multiaddr::{dnsaddr, Multiaddr, PeerId};
+//!
+//! let records = [
+//!     "dnsaddr=/ip4/1.2.3.4/tcp/4001/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC",
+//!     "dnsaddr=/ip4/5.6.7.8/tcp/4001/p2p/QmNnooDu7bfjPFoTZYxMNLWUQJyrVwtbZg5gBMjTezGAJN",
+//!     "dnsaddr=/invalid",
+//! ];
+//! let addrs = dnsaddr::parse_records(records);
+//! assert!(addrs[2].is_err());
+//!
+//! let peer: PeerId = "QmNnooDu7bfjPFoTZYxMNLWUQJyrVwtbZg5gBMjTezGAJN".parse().unwrap();
+//! let addrs = dnsaddr::for_peer(addrs.into_iter().flatten(), &peer);
+//! assert_eq!(addrs.len(), 1);
+//!
+//! assert_eq!(dnsaddr::to_record(&addrs[0]).unwrap(), records[1]);
+//! ```
+//!
+//! [`MultiaddrSlice::resolve`]: crate::MultiaddrSlice::resolve
+
+use crate::{Error, Multiaddr, MultiaddrSlice, PeerId, Protocol};
+use alloc::{format, string::String, vec::Vec};
+use core::{error, fmt};
+
+/// The prefix of every `dnsaddr` TXT record.
+pub const PREFIX: &str = "dnsaddr=";
+
+/// The maximum length in bytes of a character-string in a TXT record.
+pub const MAX_RECORD_LEN: usize = 255;
+
+/// Parses a single `dnsaddr=<multiaddr>` TXT record.
+pub fn parse_record(record: &str) -> Result<Multiaddr, DnsaddrErr> {
+    record
+        .strip_prefix(PREFIX)
+        .ok_or(DnsaddrErr::MissingPrefix)?
+        .parse()
+        .map_err(DnsaddrErr::InvalidMultiaddr)
+}
+
+/// Parses a set of TXT records, returning one result per record, in order.
+pub fn parse_records<I>(records: I) -> Vec<Result<Multiaddr, DnsaddrErr>>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    records
+        .into_iter()
+        .map(|record| parse_record(record.as_ref()))
+        .collect()
+}
+
+/// Keeps the multiaddresses that end in `/p2p/<peer>`.
+pub fn for_peer<I>(addrs: I, peer: &PeerId) -> Vec<Multiaddr>
+where
+    I: IntoIterator<Item = Multiaddr>,
+{
+    addrs
+        .into_iter()
+        .filter(|addr| matches!(addr.iter().last(), Some(Protocol::P2p(p)) if p == *peer))
+        .collect()
+}
+
+/// Generates the `dnsaddr=<multiaddr>` TXT record of a multiaddress.
+///
+/// Fails if the record does not fit into a single character-string of
+/// [`MAX_RECORD_LEN`] bytes, or if the multiaddress is empty or not represented faithfully
+/// by its string form.
+pub fn to_record(addr: &MultiaddrSlice) -> Result<String, DnsaddrErr> {
+    if addr.is_empty() {
+        return Err(DnsaddrErr::NotRepresentable);
+    }
+    let record = format!("{PREFIX}{addr}");
+    if record.len() > MAX_RECORD_LEN {
+        return Err(DnsaddrErr::TooLong(record.len()));
+    }
+    match parse_record(&record) {
+        Ok(parsed) if *parsed == *addr => Ok(record),
+        _ => Err(DnsaddrErr::NotRepresentable),
+    }
+}
+
+/// Generates the TXT records of a list of multiaddresses, returning one result per address,
+/// in order.
+pub fn to_records<'a, I>(addrs: I) -> Vec<Result<String, DnsaddrErr>>
+where
+    I: IntoIterator<Item = &'a Multiaddr>,
+{
+    addrs.into_iter().map(|addr| to_record(addr)).collect()
+}
+
+/// Error while parsing or generating a `dnsaddr` TXT record.
+#[derive(Debug)]
+pub enum DnsaddrErr {
+    /// The record does not start with `dnsaddr=`.
+    MissingPrefix,
+    /// The record does not contain a valid multiaddress.
+    InvalidMultiaddr(Error),
+    /// The record is longer than [`MAX_RECORD_LEN`] bytes.
+    TooLong(usize),
+    /// The multiaddress is empty or does not survive a round trip through its string form.
+    NotRepresentable,
+}
+
+impl fmt::Display for DnsaddrErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DnsaddrErr::MissingPrefix => write!(f, "TXT record does not start with `{PREFIX}`"),
+            DnsaddrErr::InvalidMultiaddr(e) => write!(f, "invalid multiaddr in TXT record: {e}"),
+            DnsaddrErr::TooLong(len) => {
+                write!(
+                    f,
+                    "TXT record of {len} bytes exceeds {MAX_RECORD_LEN} bytes"
+                )
+            }
+            DnsaddrErr::NotRepresentable => {
+                f.write_str("multiaddr cannot be represented as a TXT record")
+            }
+        }
+    }
+}
+
+impl error::Error for DnsaddrErr {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            DnsaddrErr::InvalidMultiaddr(e) => Some(e),
+            _ => None,
+        }
+    }
+}
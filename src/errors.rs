@@ -1,9 +1,22 @@
-use std::{error, fmt, io, net, num, str, string};
+use crate::protocol;
+use alloc::{
+    borrow::ToOwned,
+    boxed::Box,
+    string::{self, String},
+    vec::Vec,
+};
+use core::{error, fmt, net, num, str};
 use unsigned_varint::decode;
 
-pub type Result<T> = ::std::result::Result<T, Error>;
+pub type Result<T> = ::core::result::Result<T, Error>;
 
 /// Error types
+///
+/// Errors from parsing the string representation or decoding the binary representation of a
+/// multiaddress are wrapped in [`Error::InvalidComponentString`] and
+/// [`Error::InvalidComponentBytes`], which carry the position of the offending component.
+/// Use [`Error::inner`] to match on the underlying error, e.g.
+/// [`Error::UnknownProtocolString`].
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum Error {
@@ -14,6 +27,174 @@ pub enum Error {
     ParsingError(Box<dyn error::Error + Send + Sync>),
     UnknownProtocolId(u32),
     UnknownProtocolString(String),
+    /// The value of a `/tcp`, `/udp`, `/dccp` or `/sctp` component is not a port.
+    InvalidPort(num::ParseIntError),
+    /// The value of an `/ip4` or `/ip6` component is not an IP address.
+    InvalidIpAddr(net::AddrParseError),
+    /// The value of a `/p2p` component is not a peer id.
+    InvalidPeerId(Box<dyn error::Error + Send + Sync>),
+    /// The value of a `/certhash` component is not a multihash.
+    InvalidMultihash(Box<dyn error::Error + Send + Sync>),
+    /// A protocol that requires a value is the last part of the string.
+    MissingValue,
+    /// Parsing a component of the string representation of a multiaddress failed.
+    InvalidComponentString {
+        /// Index of the offending component.
+        index: usize,
+        /// Offset in characters of the offending part of the string: the value of the
+        /// component, or its protocol name if the name is unknown or the value is missing.
+        offset: usize,
+        /// Name of the protocol of the component, if it is known.
+        protocol: Option<&'static str>,
+        /// The offending value, or the protocol name if it is unknown.
+        value: Option<String>,
+        /// Why the component could not be parsed.
+        error: Box<Error>,
+    },
+    /// Decoding a component of the binary representation of a multiaddress failed.
+    InvalidComponentBytes {
+        /// Index of the offending component.
+        index: usize,
+        /// Offset in bytes of the offending component.
+        offset: usize,
+        /// Name of the protocol of the component, if its code is known.
+        protocol: Option<&'static str>,
+        /// The undecodable bytes following the protocol code, or all remaining bytes if the
+        /// code itself cannot be read.
+        value: Vec<u8>,
+        /// Why the component could not be decoded.
+        error: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Returns the index of the component that could not be parsed, if known.
+    pub fn index(&self) -> Option<usize> {
+        match self {
+            Error::InvalidComponentString { index, .. }
+            | Error::InvalidComponentBytes { index, .. } => Some(*index),
+            _ => None,
+        }
+    }
+
+    /// Returns the offset of the component that could not be parsed, if known, in characters
+    /// for the string and in bytes for the binary representation.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Error::InvalidComponentString { offset, .. }
+            | Error::InvalidComponentBytes { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+
+    /// Returns the error without the position of the offending component.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use multiaddr::{Error, Multiaddr};
+    ///
+    /// let error = "/ip4/1.2.3.4/tcp/80x".parse::<Multiaddr>().unwrap_err();
+    /// assert_eq!(error.index(), Some(1));
+    /// assert_eq!(error.offset(), Some(17));
+    /// assert!(matches!(error.inner(), Error::InvalidPort(_)));
+    /// ```
+    ///
+    pub fn inner(&self) -> &Error {
+        match self {
+            Error::InvalidComponentString { error, .. }
+            | Error::InvalidComponentBytes { error, .. } => error.inner(),
+            e => e,
+        }
+    }
+
+    /// Returns the known protocol name closest to an unknown one, if any is close enough.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use multiaddr::Multiaddr;
+    ///
+    /// let error = "/ip4/1.2.3.4/tpc/80".parse::<Multiaddr>().unwrap_err();
+    /// assert_eq!(error.suggestion(), Some("tcp"));
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "`tpc` at component 1, offset 13: unknown protocol string: tpc; did you mean `tcp`?"
+    /// );
+    /// ```
+    ///
+    pub fn suggestion(&self) -> Option<&'static str> {
+        match self.inner() {
+            Error::UnknownProtocolString(name) => protocol::closest_name(name),
+            _ => None,
+        }
+    }
+
+    /// Adds the position of the component made up of `parts` of `input` to an error of
+    /// [`Protocol::from_str_parts`](crate::Protocol::from_str_parts).
+    ///
+    /// `parts` are the byte offsets and contents of the consumed parts, starting with the
+    /// protocol name.
+    pub(crate) fn in_string(
+        input: &str,
+        index: usize,
+        parts: &[(usize, &str)],
+        error: Error,
+    ) -> Error {
+        let Some(&(name_offset, name)) = parts.first() else {
+            return error;
+        };
+        let (offset, protocol, value) = match (&error, &parts[1..]) {
+            (Error::UnknownProtocolString(_), _) => (name_offset, None, Some(name.to_owned())),
+            (_, []) => (name_offset, protocol::known_name(name), None),
+            (_, [(start, _), ..]) => {
+                let (last, part) = parts[parts.len() - 1];
+                let value = &input[*start..last + part.len()];
+                (*start, protocol::known_name(name), Some(value.to_owned()))
+            }
+        };
+        Error::InvalidComponentString {
+            index,
+            offset: input[..offset].chars().count(),
+            protocol,
+            value,
+            error: Box::new(error),
+        }
+    }
+
+    /// Adds the position of the component at the start of `rest` to an error of
+    /// [`Protocol::from_bytes`](crate::Protocol::from_bytes).
+    pub(crate) fn in_bytes(index: usize, offset: usize, rest: &[u8], error: Error) -> Error {
+        let (protocol, value) = match decode::u32(rest) {
+            Ok((code, value)) => (protocol::name_of(code), value),
+            Err(_) => (None, rest),
+        };
+        Error::InvalidComponentBytes {
+            index,
+            offset,
+            protocol,
+            value: value.to_vec(),
+            error: Box::new(error),
+        }
+    }
+}
+
+/// Boxes an error of a dependency that only implements [`error::Error`] with its `std`
+/// feature.
+#[cfg(feature = "std")]
+pub(crate) fn boxed<E>(e: E) -> Box<dyn error::Error + Send + Sync>
+where
+    E: error::Error + Send + Sync + 'static,
+{
+    Box::new(e)
+}
+
+/// Boxes an error of a dependency that only implements [`error::Error`] with its `std`
+/// feature.
+#[cfg(not(feature = "std"))]
+pub(crate) fn boxed<E: fmt::Display>(e: E) -> Box<dyn error::Error + Send + Sync> {
+    use alloc::string::ToString;
+    e.to_string().into()
 }
 
 impl fmt::Display for Error {
@@ -28,6 +209,43 @@ impl fmt::Display for Error {
             Error::UnknownProtocolString(string) => {
                 write!(f, "unknown protocol string: {string}")
             }
+            Error::InvalidPort(e) => write!(f, "invalid port: {e}"),
+            Error::InvalidIpAddr(e) => write!(f, "invalid ip address: {e}"),
+            Error::InvalidPeerId(e) => write!(f, "invalid peer id: {e}"),
+            Error::InvalidMultihash(e) => write!(f, "invalid multihash: {e}"),
+            Error::MissingValue => f.write_str("missing value"),
+            Error::InvalidComponentString {
+                index,
+                offset,
+                protocol,
+                value,
+                error,
+            } => {
+                match (protocol, value) {
+                    (Some(protocol), Some(value)) => write!(f, "`{value}` of `/{protocol}`")?,
+                    (Some(protocol), None) => write!(f, "`/{protocol}`")?,
+                    (None, Some(value)) => write!(f, "`{value}`")?,
+                    (None, None) => f.write_str("component")?,
+                }
+                write!(f, " at component {index}, offset {offset}: {error}")?;
+                match self.suggestion() {
+                    Some(suggestion) => write!(f, "; did you mean `{suggestion}`?"),
+                    None => Ok(()),
+                }
+            }
+            Error::InvalidComponentBytes {
+                index,
+                offset,
+                protocol,
+                error,
+                ..
+            } => match protocol {
+                Some(protocol) => write!(
+                    f,
+                    "`/{protocol}` at component {index}, byte offset {offset}: {error}"
+                ),
+                None => write!(f, "component {index} at byte offset {offset}: {error}"),
+            },
         }
     }
 }
@@ -35,16 +253,22 @@ impl fmt::Display for Error {
 impl error::Error for Error {
     #[inline]
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
-        if let Error::ParsingError(e) = self {
-            Some(&**e)
-        } else {
-            None
+        match self {
+            Error::ParsingError(e) | Error::InvalidPeerId(e) | Error::InvalidMultihash(e) => {
+                Some(&**e)
+            }
+            Error::InvalidPort(e) => Some(e),
+            Error::InvalidIpAddr(e) => Some(e),
+            Error::InvalidComponentString { error, .. }
+            | Error::InvalidComponentBytes { error, .. } => Some(&**error),
+            _ => None,
         }
     }
 }
 
-impl From<io::Error> for Error {
-    fn from(err: io::Error) -> Error {
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Error {
         Error::ParsingError(err.into())
     }
 }
@@ -57,7 +281,7 @@ impl From<multihash::Error> for Error {
 
 impl From<multibase::Error> for Error {
     fn from(err: multibase::Error) -> Error {
-        Error::ParsingError(err.into())
+        Error::ParsingError(boxed(err))
     }
 }
 
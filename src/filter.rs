@@ -0,0 +1,418 @@
+use crate::{Multiaddr, MultiaddrSlice, Protocol};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    error, fmt,
+    net::{IpAddr, Ipv6Addr},
+    result::Result as StdResult,
+};
+#[cfg(feature = "serde")]
+use serde::{
+    de::{self, MapAccess, SeqAccess, Visitor},
+    ser::{SerializeMap, SerializeStruct},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+/// What a [`MultiaddrFilter`] does with a matching address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Accept,
+    Deny,
+}
+
+/// A set of accept and deny rules over IP subnets, used to decide which addresses may be
+/// dialed or listened on.
+///
+/// Each rule covers a subnet given as `/ip4/<net>/ipcidr/<prefix>` or
+/// `/ip6/<net>/ipcidr/<prefix>`. An address is matched by the leading `/ip4` or `/ip6`
+/// component, skipping any `/ip6zone`, and the most specific matching rule decides. If no
+/// rule matches, or the address does not start with an IP address, the default action
+/// applies. IPv4-mapped IPv6 addresses are matched against IPv4 rules and vice versa.
+/// IPv4 addresses are only covered by IPv4 rules and by IPv6 rules within `::ffff:0:0/96`, so
+/// that e.g. denying `/ip6/::/ipcidr/0` does not deny any IPv4 address.
+///
+/// Filters serialize as their default action and a map from subnet to action.
+///
+/// # Example
+///
+/// ```
+/// use multiaddr::{Action, Multiaddr, MultiaddrFilter};
+///
+/// let mut filter = MultiaddrFilter::new(Action::Accept);
+/// filter.add_rule(&"/ip4/10.0.0.0/ipcidr/8".parse::<Multiaddr>().unwrap(), Action::Deny).unwrap();
+/// filter.add_rule(&"/ip4/10.1.0.0/ipcidr/16".parse::<Multiaddr>().unwrap(), Action::Accept).unwrap();
+///
+/// assert!(!filter.is_allowed(&"/ip4/10.2.3.4/tcp/80".parse::<Multiaddr>().unwrap()));
+/// assert!(filter.is_allowed(&"/ip4/10.1.2.3/tcp/80".parse::<Multiaddr>().unwrap()));
+/// assert!(!filter.is_allowed(&"/ip6/::ffff:10.2.3.4/tcp/80".parse::<Multiaddr>().unwrap()));
+/// assert!(filter.is_allowed(&"/ip4/192.168.1.1/tcp/80".parse::<Multiaddr>().unwrap()));
+/// ```
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiaddrFilter {
+    default: Action,
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    /// The subnet as given, with the host bits cleared.
+    subnet: Multiaddr,
+    /// The network in the IPv6 address space, IPv4 networks are IPv4-mapped.
+    net: u128,
+    /// The prefix length in the IPv6 address space.
+    prefix: u8,
+    action: Action,
+}
+
+impl MultiaddrFilter {
+    /// Creates a filter without rules, applying `default` to all addresses.
+    pub fn new(default: Action) -> Self {
+        MultiaddrFilter {
+            default,
+            rules: Vec::new(),
+        }
+    }
+
+    /// Returns the action applied to addresses that match no rule.
+    pub fn default_action(&self) -> Action {
+        self.default
+    }
+
+    /// Sets the action applied to addresses that match no rule.
+    pub fn set_default_action(&mut self, action: Action) {
+        self.default = action
+    }
+
+    /// Adds a rule for a subnet of the form `/ip4/<net>/ipcidr/<prefix>` or
+    /// `/ip6/<net>/ipcidr/<prefix>`.
+    ///
+    /// Host bits set in `<net>` are ignored. If there already is a rule for the same
+    /// subnet, its action is replaced.
+    pub fn add_rule(
+        &mut self,
+        subnet: &MultiaddrSlice,
+        action: Action,
+    ) -> StdResult<(), FilterError> {
+        let (subnet, net, prefix) = parse_subnet(subnet)?;
+        match self
+            .rules
+            .iter_mut()
+            .find(|r| r.net == net && r.prefix == prefix)
+        {
+            Some(rule) => {
+                rule.subnet = subnet;
+                rule.action = action;
+            }
+            None => self.rules.push(Rule {
+                subnet,
+                net,
+                prefix,
+                action,
+            }),
+        }
+        Ok(())
+    }
+
+    /// Removes the rule for a subnet, returning its action if there was one.
+    pub fn remove_rule(&mut self, subnet: &MultiaddrSlice) -> Option<Action> {
+        let (_, net, prefix) = parse_subnet(subnet).ok()?;
+        let index = self
+            .rules
+            .iter()
+            .position(|r| r.net == net && r.prefix == prefix)?;
+        Some(self.rules.remove(index).action)
+    }
+
+    /// Returns the rules in the order they were added.
+    pub fn rules(&self) -> impl Iterator<Item = (&MultiaddrSlice, Action)> {
+        self.rules.iter().map(|r| (&*r.subnet, r.action))
+    }
+
+    /// Returns the action of the most specific rule matching `addr`, or the default action.
+    pub fn action(&self, addr: &MultiaddrSlice) -> Action {
+        let ip = addr
+            .iter()
+            .find(|p| !matches!(p, Protocol::Ip6zone(_)))
+            .and_then(|p| match p {
+                Protocol::Ip4(ip) => Some(IpAddr::V4(ip)),
+                Protocol::Ip6(ip) => Some(IpAddr::V6(ip)),
+                _ => None,
+            });
+        match ip {
+            Some(ip) => self.ip_action(ip),
+            None => self.default,
+        }
+    }
+
+    /// Returns the action of the most specific rule matching `ip`, or the default action.
+    pub fn ip_action(&self, ip: IpAddr) -> Action {
+        let ip = to_u128(ip);
+        self.rules
+            .iter()
+            .filter(|r| prefix_matches(ip, r.net, r.prefix))
+            .max_by_key(|r| r.prefix)
+            .map_or(self.default, |r| r.action)
+    }
+
+    /// Checks whether `addr` may be dialed or listened on.
+    pub fn is_allowed(&self, addr: &MultiaddrSlice) -> bool {
+        self.action(addr) == Action::Accept
+    }
+}
+
+impl Default for MultiaddrFilter {
+    /// Creates a filter without rules that accepts all addresses.
+    fn default() -> Self {
+        MultiaddrFilter::new(Action::Accept)
+    }
+}
+
+fn to_u128(ip: IpAddr) -> u128 {
+    match ip {
+        IpAddr::V4(ip) => ip.to_ipv6_mapped().into(),
+        IpAddr::V6(ip) => ip.into(),
+    }
+}
+
+fn mask(prefix: u8) -> u128 {
+    u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0)
+}
+
+/// The IPv4-mapped IPv6 addresses, `::ffff:0:0/96`, into which IPv4 addresses are mapped.
+const IPV4_MAPPED: (u128, u8) = (0xffff_0000_0000, 96);
+
+fn prefix_matches(ip: u128, net: u128, prefix: u8) -> bool {
+    // IPv6 rules broader than `::ffff:0:0/96` do not cover IPv4 addresses.
+    let (mapped, mapped_prefix) = IPV4_MAPPED;
+    if prefix < mapped_prefix && ip & mask(mapped_prefix) == mapped {
+        return false;
+    }
+    ip & mask(prefix) == net
+}
+
+fn parse_subnet(subnet: &MultiaddrSlice) -> StdResult<(Multiaddr, u128, u8), FilterError> {
+    let mut iter = subnet.iter();
+    let (ip, prefix) = match (iter.next(), iter.next(), iter.next()) {
+        (Some(Protocol::Ip4(ip)), Some(Protocol::Ipcidr(prefix)), None) => {
+            if prefix > 32 {
+                return Err(FilterError::InvalidPrefix(prefix));
+            }
+            (IpAddr::V4(ip), prefix + 96)
+        }
+        (Some(Protocol::Ip6(ip)), Some(Protocol::Ipcidr(prefix)), None) => {
+            if prefix > 128 {
+                return Err(FilterError::InvalidPrefix(prefix));
+            }
+            (IpAddr::V6(ip), prefix)
+        }
+        _ => return Err(FilterError::NotASubnet(subnet.to_string())),
+    };
+    let net = to_u128(ip) & mask(prefix);
+    let ip = match ip {
+        IpAddr::V4(_) => Protocol::Ip4(
+            Ipv6Addr::from(net)
+                .to_ipv4_mapped()
+                .expect("IPv4 networks are IPv4-mapped"),
+        ),
+        IpAddr::V6(_) => Protocol::Ip6(net.into()),
+    };
+    let subnet = Multiaddr::empty()
+        .with(ip)
+        .with(subnet.iter().nth(1).expect("checked above"));
+    Ok((subnet, net, prefix))
+}
+
+/// Error while adding a rule to a [`MultiaddrFilter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterError {
+    /// The multiaddress is not of the form `/ip4/<net>/ipcidr/<prefix>` or
+    /// `/ip6/<net>/ipcidr/<prefix>`.
+    NotASubnet(String),
+    /// The prefix is longer than the IP address.
+    InvalidPrefix(u8),
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterError::NotASubnet(addr) => write!(f, "`{addr}` is not an ip subnet"),
+            FilterError::InvalidPrefix(prefix) => write!(f, "invalid subnet prefix: {prefix}"),
+        }
+    }
+}
+
+impl error::Error for FilterError {}
+
+#[cfg(feature = "serde")]
+impl Action {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Action::Accept => "accept",
+            Action::Deny => "deny",
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Action {
+    fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Action {
+    fn deserialize<D>(deserializer: D) -> StdResult<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct Visitor;
+
+        impl<'de> de::Visitor<'de> for Visitor {
+            type Value = Action;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("`accept` or `deny`")
+            }
+            fn visit_str<E: de::Error>(self, v: &str) -> StdResult<Self::Value, E> {
+                match v {
+                    "accept" => Ok(Action::Accept),
+                    "deny" => Ok(Action::Deny),
+                    _ => Err(E::unknown_variant(v, &["accept", "deny"])),
+                }
+            }
+        }
+
+        deserializer.deserialize_str(Visitor)
+    }
+}
+
+/// The rules of a filter, serialized as a map from subnet to action.
+#[cfg(feature = "serde")]
+struct Rules<'a>(&'a [Rule]);
+
+#[cfg(feature = "serde")]
+impl Serialize for Rules<'_> {
+    fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for rule in self.0 {
+            map.serialize_entry(&rule.subnet, &rule.action)?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for MultiaddrFilter {
+    fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut filter = serializer.serialize_struct("MultiaddrFilter", 2)?;
+        filter.serialize_field("default", &self.default)?;
+        filter.serialize_field("rules", &Rules(&self.rules))?;
+        filter.end()
+    }
+}
+
+/// The rules of a filter as deserialized, before they are validated.
+#[cfg(feature = "serde")]
+struct RawRules(Vec<(Multiaddr, Action)>);
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for RawRules {
+    fn deserialize<D>(deserializer: D) -> StdResult<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct RulesVisitor;
+
+        impl<'de> Visitor<'de> for RulesVisitor {
+            type Value = RawRules;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a map from subnet to action")
+            }
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> StdResult<Self::Value, A::Error> {
+                let mut rules = Vec::with_capacity(map.size_hint().unwrap_or(0));
+                while let Some(rule) = map.next_entry()? {
+                    rules.push(rule);
+                }
+                Ok(RawRules(rules))
+            }
+        }
+
+        deserializer.deserialize_map(RulesVisitor)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for MultiaddrFilter {
+    fn deserialize<D>(deserializer: D) -> StdResult<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        const FIELDS: &[&str] = &["default", "rules"];
+
+        struct FilterVisitor;
+
+        impl FilterVisitor {
+            fn build<E: de::Error>(
+                default: Action,
+                rules: RawRules,
+            ) -> StdResult<MultiaddrFilter, E> {
+                let mut filter = MultiaddrFilter::new(default);
+                for (subnet, action) in rules.0 {
+                    filter.add_rule(&subnet, action).map_err(E::custom)?;
+                }
+                Ok(filter)
+            }
+        }
+
+        impl<'de> Visitor<'de> for FilterVisitor {
+            type Value = MultiaddrFilter;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a multiaddr filter")
+            }
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> StdResult<Self::Value, A::Error> {
+                let default = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let rules = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                Self::build(default, rules)
+            }
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> StdResult<Self::Value, A::Error> {
+                let mut default = None;
+                let mut rules = None;
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "default" if default.is_none() => default = Some(map.next_value()?),
+                        "rules" if rules.is_none() => rules = Some(map.next_value()?),
+                        "default" | "rules" => {
+                            return Err(de::Error::custom(format_args!("duplicate field `{key}`")))
+                        }
+                        _ => return Err(de::Error::unknown_field(&key, FIELDS)),
+                    }
+                }
+                Self::build(
+                    default.unwrap_or(Action::Accept),
+                    rules.unwrap_or(RawRules(Vec::new())),
+                )
+            }
+        }
+
+        deserializer.deserialize_struct("MultiaddrFilter", FIELDS, FilterVisitor)
+    }
+}
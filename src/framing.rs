@@ -0,0 +1,211 @@
+//! Multiaddresses framed by an unsigned varint length prefix, as sent by e.g. identify and
+//! peer exchange.
+
+use crate::{Error, Multiaddr, MultiaddrSlice};
+#[cfg(any(feature = "tokio-util", feature = "asynchronous-codec"))]
+use bytes::{Buf, BufMut, BytesMut};
+use std::{
+    error, fmt,
+    io::{self, Read, Write},
+};
+#[cfg(any(feature = "tokio-util", feature = "asynchronous-codec"))]
+use unsigned_varint::decode;
+use unsigned_varint::{encode, io::read_usize};
+
+/// The default maximum length in bytes of a length-prefixed multiaddress.
+pub const DEFAULT_MAX_FRAME_LEN: usize = 1024;
+
+impl MultiaddrSlice {
+    /// Writes this multiaddress prefixed by its length as an unsigned varint.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use multiaddr::{Multiaddr, DEFAULT_MAX_FRAME_LEN};
+    ///
+    /// let addr: Multiaddr = "/ip4/1.2.3.4/tcp/80".parse().unwrap();
+    /// let mut buf = Vec::new();
+    /// addr.write_length_prefixed(&mut buf).unwrap();
+    /// assert_eq!(buf[0] as usize, addr.len());
+    ///
+    /// let read = Multiaddr::read_length_prefixed(&mut &buf[..], DEFAULT_MAX_FRAME_LEN).unwrap();
+    /// assert_eq!(read, addr);
+    /// ```
+    ///
+    pub fn write_length_prefixed<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        let mut len = encode::usize_buffer();
+        writer.write_all(encode::usize(self.len(), &mut len))?;
+        writer.write_all(self.as_ref())
+    }
+}
+
+impl Multiaddr {
+    /// Reads a multiaddress prefixed by its length as an unsigned varint.
+    ///
+    /// Fails without reading the multiaddress if its length exceeds `max_len` bytes.
+    pub fn read_length_prefixed<R: Read + ?Sized>(
+        reader: &mut R,
+        max_len: usize,
+    ) -> Result<Multiaddr, FramingError> {
+        let len = read_usize(&mut *reader).map_err(|e| FramingError::Io(e.into()))?;
+        if len > max_len {
+            return Err(FramingError::TooLong { len, max_len });
+        }
+        let mut bytes = vec![0; len];
+        reader.read_exact(&mut bytes)?;
+        Multiaddr::try_from(bytes).map_err(FramingError::InvalidMultiaddr)
+    }
+}
+
+/// An `Encoder` and `Decoder` of length-prefixed multiaddresses, for `tokio_util::codec`
+/// with the `tokio-util` feature and for `asynchronous_codec` with the `asynchronous-codec`
+/// feature.
+#[cfg(any(feature = "tokio-util", feature = "asynchronous-codec"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MultiaddrCodec {
+    max_len: usize,
+}
+
+#[cfg(any(feature = "tokio-util", feature = "asynchronous-codec"))]
+impl MultiaddrCodec {
+    /// Creates a codec accepting multiaddresses of up to `max_len` bytes.
+    pub fn new(max_len: usize) -> Self {
+        MultiaddrCodec { max_len }
+    }
+
+    /// Returns the maximum length in bytes of the multiaddresses of this codec.
+    pub fn max_len(&self) -> usize {
+        self.max_len
+    }
+
+    fn encode_frame(&self, addr: &MultiaddrSlice, dst: &mut BytesMut) -> Result<(), FramingError> {
+        if addr.len() > self.max_len {
+            return Err(FramingError::TooLong {
+                len: addr.len(),
+                max_len: self.max_len,
+            });
+        }
+        let mut len = encode::usize_buffer();
+        let len = encode::usize(addr.len(), &mut len);
+        dst.reserve(len.len() + addr.len());
+        dst.put_slice(len);
+        dst.put_slice(addr.as_ref());
+        Ok(())
+    }
+
+    fn decode_frame(&self, src: &mut BytesMut) -> Result<Option<Multiaddr>, FramingError> {
+        let (len, rest) = match decode::usize(&src[..]) {
+            Ok(decoded) => decoded,
+            Err(decode::Error::Insufficient) => return Ok(None),
+            Err(e) => {
+                return Err(FramingError::Io(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    e,
+                )))
+            }
+        };
+        if len > self.max_len {
+            return Err(FramingError::TooLong {
+                len,
+                max_len: self.max_len,
+            });
+        }
+        let prefix = src.len() - rest.len();
+        if rest.len() < len {
+            src.reserve(prefix + len - src.len());
+            return Ok(None);
+        }
+        src.advance(prefix);
+        let bytes = src.split_to(len).freeze();
+        Multiaddr::try_from(bytes)
+            .map(Some)
+            .map_err(FramingError::InvalidMultiaddr)
+    }
+}
+
+#[cfg(any(feature = "tokio-util", feature = "asynchronous-codec"))]
+impl Default for MultiaddrCodec {
+    fn default() -> Self {
+        MultiaddrCodec::new(DEFAULT_MAX_FRAME_LEN)
+    }
+}
+
+#[cfg(feature = "tokio-util")]
+impl tokio_util::codec::Encoder<Multiaddr> for MultiaddrCodec {
+    type Error = FramingError;
+
+    fn encode(&mut self, addr: Multiaddr, dst: &mut BytesMut) -> Result<(), FramingError> {
+        self.encode_frame(&addr, dst)
+    }
+}
+
+#[cfg(feature = "tokio-util")]
+impl tokio_util::codec::Decoder for MultiaddrCodec {
+    type Item = Multiaddr;
+    type Error = FramingError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Multiaddr>, FramingError> {
+        self.decode_frame(src)
+    }
+}
+
+#[cfg(feature = "asynchronous-codec")]
+impl asynchronous_codec::Encoder for MultiaddrCodec {
+    type Item<'a> = Multiaddr;
+    type Error = FramingError;
+
+    fn encode(&mut self, addr: Multiaddr, dst: &mut BytesMut) -> Result<(), FramingError> {
+        self.encode_frame(&addr, dst)
+    }
+}
+
+#[cfg(feature = "asynchronous-codec")]
+impl asynchronous_codec::Decoder for MultiaddrCodec {
+    type Item = Multiaddr;
+    type Error = FramingError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Multiaddr>, FramingError> {
+        self.decode_frame(src)
+    }
+}
+
+/// Error while reading or decoding a length-prefixed multiaddress.
+#[derive(Debug)]
+pub enum FramingError {
+    /// Reading failed or the length prefix is not a valid varint.
+    Io(io::Error),
+    /// The multiaddress is longer than the maximum length.
+    TooLong { len: usize, max_len: usize },
+    /// The frame does not contain a valid multiaddress.
+    InvalidMultiaddr(Error),
+}
+
+impl fmt::Display for FramingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FramingError::Io(e) => write!(f, "failed to read multiaddr: {e}"),
+            FramingError::TooLong { len, max_len } => {
+                write!(f, "multiaddr of {len} bytes exceeds {max_len} bytes")
+            }
+            FramingError::InvalidMultiaddr(e) => {
+                write!(f, "invalid length-prefixed multiaddr: {e}")
+            }
+        }
+    }
+}
+
+impl error::Error for FramingError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            FramingError::Io(e) => Some(e),
+            FramingError::InvalidMultiaddr(e) => Some(e),
+            FramingError::TooLong { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for FramingError {
+    fn from(e: io::Error) -> Self {
+        FramingError::Io(e)
+    }
+}
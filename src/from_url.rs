@@ -1,11 +1,12 @@
 use crate::{Multiaddr, Protocol};
-use std::{error, fmt, iter, net::IpAddr};
+use std::{borrow::Cow, error, fmt, iter};
 
 /// Attempts to parse an URL into a multiaddress.
 ///
 /// This function will return an error if some information in the URL cannot be retained in the
-/// generated multiaddress. This includes a username, password, path (if not supported by the
-/// multiaddr), and query string.
+/// generated multiaddress. This includes a username, password, query string and fragment.
+/// The path of an `http` or `https` URL becomes an `/http-path`, the one of a `ws` or `wss` URL
+/// the path of the `/ws` or `/wss`.
 ///
 /// This function is only present if the `url` feature is enabled, and it is
 /// enabled by default.
@@ -23,6 +24,9 @@ use std::{error, fmt, iter, net::IpAddr};
 /// ```
 /// let addr = multiaddr::from_url("ws://127.0.0.1:8080/").unwrap();
 /// assert_eq!(addr, "/ip4/127.0.0.1/tcp/8080/ws".parse().unwrap());
+///
+/// let addr = multiaddr::from_url("http://127.0.0.1/api/v0").unwrap();
+/// assert_eq!(addr, "/ip4/127.0.0.1/tcp/80/http/http-path/api%2Fv0".parse().unwrap());
 /// ```
 ///
 pub fn from_url(url: &str) -> std::result::Result<Multiaddr, FromUrlErr> {
@@ -66,29 +70,33 @@ fn from_url_inner_http_ws(
     url: url::Url,
     lossy: bool,
 ) -> std::result::Result<Multiaddr, FromUrlErr> {
-    let (protocol, lost_path, default_port) = match url.scheme() {
-        "ws" => (Protocol::Ws(url.path().to_owned().into()), false, 80),
-        "wss" => (Protocol::Wss(url.path().to_owned().into()), false, 443),
-        "http" => (Protocol::Http, true, 80),
-        "https" => (Protocol::Https, true, 443),
+    let path = percent_encoding::percent_decode_str(url.path()).decode_utf8();
+    let lost_path = path.is_err();
+    let path = path.unwrap_or_else(|_| Cow::Borrowed(url.path()));
+    let http_path = match path.strip_prefix('/').unwrap_or(&path) {
+        "" => None,
+        path => Some(Protocol::HttpPath(path.to_owned().into())),
+    };
+    let (protocol, http_path, default_port) = match url.scheme() {
+        "ws" => (Protocol::Ws(path.into_owned().into()), None, 80),
+        "wss" => (Protocol::Wss(path.into_owned().into()), None, 443),
+        "http" => (Protocol::Http, http_path, 80),
+        "https" => (Protocol::Https, http_path, 443),
         _ => unreachable!("We only call this function for one of the given schemes; qed"),
     };
 
     let port = Protocol::Tcp(url.port().unwrap_or(default_port));
-    let ip = if let Some(hostname) = url.host_str() {
-        if let Ok(ip) = hostname.parse::<IpAddr>() {
-            Protocol::from(ip)
-        } else {
-            Protocol::Dns(hostname.into())
-        }
-    } else {
-        return Err(FromUrlErr::BadUrl);
+    let ip = match url.host() {
+        Some(url::Host::Ipv4(ip)) => Protocol::Ip4(ip),
+        Some(url::Host::Ipv6(ip)) => Protocol::Ip6(ip),
+        Some(url::Host::Domain(hostname)) => Protocol::Dns(hostname.to_owned().into()),
+        None => return Err(FromUrlErr::BadUrl),
     };
 
     if !lossy
         && (!url.username().is_empty()
             || url.password().is_some()
-            || (lost_path && url.path() != "/" && !url.path().is_empty())
+            || lost_path
             || url.query().is_some()
             || url.fragment().is_some())
     {
@@ -98,19 +106,24 @@ fn from_url_inner_http_ws(
     Ok(iter::once(ip)
         .chain(iter::once(port))
         .chain(iter::once(protocol))
+        .chain(http_path)
         .collect())
 }
 
 /// Called when `url.scheme()` is a path-like URL.
 fn from_url_inner_path(url: url::Url, lossy: bool) -> std::result::Result<Multiaddr, FromUrlErr> {
+    let path = percent_encoding::percent_decode_str(url.path()).decode_utf8();
+    let lost_path = path.is_err();
+    let path = path.unwrap_or_else(|_| Cow::Borrowed(url.path()));
     let protocol = match url.scheme() {
-        "unix" => Protocol::Unix(url.path().to_owned().into()),
+        "unix" => Protocol::Unix(path.into_owned().into()),
         _ => unreachable!("We only call this function for one of the given schemes; qed"),
     };
 
     if !lossy
         && (!url.username().is_empty()
             || url.password().is_some()
+            || lost_path
             || url.query().is_some()
             || url.fragment().is_some())
     {
@@ -143,9 +156,142 @@ impl fmt::Display for FromUrlErr {
 
 impl error::Error for FromUrlErr {}
 
+/// Characters that must be escaped in an IPv6 zone identifier (RFC 6874).
+const ZONE_ENCODE_SET: &percent_encoding::AsciiSet = &percent_encoding::NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Characters that must be escaped when an `/http-path` value, the path of a `/ws` or the path
+/// of a `/unix` is turned into an URL path.
+const URL_PATH_ENCODE_SET: &percent_encoding::AsciiSet = &percent_encoding::CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Underlying implementation of [`Multiaddr::to_url`].
+pub(crate) fn to_url(addr: &Multiaddr) -> std::result::Result<String, ToUrlErr> {
+    let mut iter = addr.iter().peekable();
+
+    let host = match iter.next().ok_or(ToUrlErr::Incomplete)? {
+        Protocol::Unix(path) => {
+            return match iter.next() {
+                None => Ok(format!(
+                    "unix:{}",
+                    percent_encoding::utf8_percent_encode(&path, URL_PATH_ENCODE_SET)
+                )),
+                Some(p) => Err(ToUrlErr::UnsupportedProtocol(p.tag())),
+            };
+        }
+        Protocol::Ip4(ip) => ip.to_string(),
+        Protocol::Ip6(ip) => match iter.peek() {
+            Some(Protocol::Ip6zone(zone)) => {
+                let host = format_ip6_zone(&ip.to_string(), zone);
+                iter.next();
+                host
+            }
+            _ => format!("[{ip}]"),
+        },
+        Protocol::Ip6zone(zone) => match iter.next().ok_or(ToUrlErr::Incomplete)? {
+            Protocol::Ip6(ip) => format_ip6_zone(&ip.to_string(), &zone),
+            p => return Err(ToUrlErr::UnsupportedProtocol(p.tag())),
+        },
+        Protocol::Dns(name) | Protocol::Dns4(name) | Protocol::Dns6(name) => name.into_owned(),
+        p => return Err(ToUrlErr::UnsupportedProtocol(p.tag())),
+    };
+
+    let port = match iter.next().ok_or(ToUrlErr::Incomplete)? {
+        Protocol::Tcp(port) => port,
+        p => return Err(ToUrlErr::UnsupportedProtocol(p.tag())),
+    };
+
+    let (scheme, path) = match iter.next().ok_or(ToUrlErr::Incomplete)? {
+        Protocol::Tls => match iter.next().ok_or(ToUrlErr::Incomplete)? {
+            Protocol::Http => ("https", http_path(&mut iter)),
+            Protocol::Ws(path) if path == "/" => ("wss", http_path(&mut iter)),
+            Protocol::Ws(path) => ("wss", path),
+            p => return Err(ToUrlErr::UnsupportedProtocol(p.tag())),
+        },
+        Protocol::Http => ("http", http_path(&mut iter)),
+        Protocol::Https => ("https", http_path(&mut iter)),
+        Protocol::Ws(path) if path == "/" => ("ws", http_path(&mut iter)),
+        Protocol::Ws(path) => ("ws", path),
+        Protocol::Wss(path) if path == "/" => ("wss", http_path(&mut iter)),
+        Protocol::Wss(path) => ("wss", path),
+        p => return Err(ToUrlErr::UnsupportedProtocol(p.tag())),
+    };
+
+    if let Some(p) = iter.next() {
+        return Err(ToUrlErr::UnsupportedProtocol(p.tag()));
+    }
+
+    let default_port = match scheme {
+        "http" | "ws" => 80,
+        _ => 443,
+    };
+    let separator = if path.starts_with('/') { "" } else { "/" };
+    let path = percent_encoding::utf8_percent_encode(&path, URL_PATH_ENCODE_SET);
+    if port == default_port {
+        Ok(format!("{scheme}://{host}{separator}{path}"))
+    } else {
+        Ok(format!("{scheme}://{host}:{port}{separator}{path}"))
+    }
+}
+
+/// Formats an IPv6 host with a zone identifier as described in RFC 6874.
+fn format_ip6_zone(ip: &str, zone: &str) -> String {
+    let zone = percent_encoding::utf8_percent_encode(zone, ZONE_ENCODE_SET);
+    format!("[{ip}%25{zone}]")
+}
+
+/// Consumes an optional `/http-path` following `/http` or `/ws` and returns it as an URL
+/// path, i.e. with a leading `/`.
+fn http_path<'a, I>(iter: &mut iter::Peekable<I>) -> Cow<'a, str>
+where
+    I: Iterator<Item = Protocol<'a>>,
+{
+    match iter.next_if(|p| matches!(p, Protocol::HttpPath(_))) {
+        Some(Protocol::HttpPath(path)) => Cow::Owned(format!("/{path}")),
+        _ => Cow::Borrowed("/"),
+    }
+}
+
+/// Error while turning a multiaddress into an URL.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ToUrlErr {
+    /// The multiaddress ends before it describes a complete URL.
+    Incomplete,
+    /// The multiaddress contains a protocol that has no URL representation at its position,
+    /// for example `/quic-v1` or a trailing `/p2p`.
+    UnsupportedProtocol(&'static str),
+}
+
+impl fmt::Display for ToUrlErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToUrlErr::Incomplete => write!(f, "Multiaddr does not describe a complete URL"),
+            ToUrlErr::UnsupportedProtocol(tag) => {
+                write!(f, "Protocol `{tag}` cannot be represented in an URL")
+            }
+        }
+    }
+}
+
+impl error::Error for ToUrlErr {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use quickcheck::{Arbitrary, Gen, QuickCheck};
+    use std::net::{Ipv4Addr, Ipv6Addr};
 
     #[test]
     fn parse_garbage_doesnt_panic() {
@@ -253,11 +399,33 @@ mod tests {
 
     #[test]
     fn path_lossy() {
-        let addr = "http://example.com:1000/foo";
+        let addr = "http://example.com:1000/%FF";
         assert!(from_url(addr).is_err());
         assert!(from_url_lossy(addr).is_ok());
     }
 
+    #[test]
+    fn http_path() {
+        let addr = from_url("http://example.com:1000/foo/bar").unwrap();
+        assert_eq!(
+            addr,
+            "/dns/example.com/tcp/1000/http/http-path/foo%2Fbar"
+                .parse()
+                .unwrap()
+        );
+
+        let addr = from_url("https://example.com/a%20b").unwrap();
+        assert_eq!(
+            addr,
+            "/dns/example.com/tcp/443/https/http-path/a%20b"
+                .parse()
+                .unwrap()
+        );
+
+        let addr = from_url("http://example.com/").unwrap();
+        assert_eq!(addr, "/dns/example.com/tcp/80/http".parse().unwrap());
+    }
+
     #[test]
     fn fragment_lossy() {
         let addr = "http://example.com:1000/#foo";
@@ -295,4 +463,262 @@ mod tests {
         let addr = from_url("wss://1.2.3.4:1000").unwrap();
         assert_eq!(addr, "/ip4/1.2.3.4/tcp/1000/wss".parse().unwrap());
     }
+
+    #[test]
+    fn to_url_ws() {
+        let addr: Multiaddr = "/ip4/127.0.0.1/tcp/8000/ws".parse().unwrap();
+        assert_eq!(addr.to_url().unwrap(), "ws://127.0.0.1:8000/");
+
+        let addr: Multiaddr = "/ip4/1.2.3.4/tcp/1000/x-parity-ws/%2ffoo%2fbar"
+            .parse()
+            .unwrap();
+        assert_eq!(addr.to_url().unwrap(), "ws://1.2.3.4:1000/foo/bar");
+    }
+
+    #[test]
+    fn to_url_reserved_characters() {
+        let addr = Multiaddr::empty()
+            .with(Protocol::Ip4(Ipv4Addr::new(1, 2, 3, 4)))
+            .with(Protocol::Tcp(80));
+
+        let ws = addr.clone().with(Protocol::Ws("/foo?bar#x".into()));
+        assert_eq!(ws.to_url().unwrap(), "ws://1.2.3.4/foo%3Fbar%23x");
+        assert_eq!(from_url(&ws.to_url().unwrap()).unwrap(), ws);
+
+        let ws = addr.clone().with(Protocol::Ws("/a b/100%".into()));
+        assert_eq!(ws.to_url().unwrap(), "ws://1.2.3.4/a%20b/100%25");
+        assert_eq!(from_url(&ws.to_url().unwrap()).unwrap(), ws);
+
+        let http = addr
+            .with(Protocol::Http)
+            .with(Protocol::HttpPath("search?q=#1".into()));
+        assert_eq!(http.to_url().unwrap(), "http://1.2.3.4/search%3Fq=%231");
+        assert_eq!(from_url(&http.to_url().unwrap()).unwrap(), http);
+    }
+
+    #[test]
+    fn to_url_default_ports() {
+        let addr: Multiaddr = "/dns/example.com/tcp/80/http".parse().unwrap();
+        assert_eq!(addr.to_url().unwrap(), "http://example.com/");
+
+        let addr: Multiaddr = "/dns/example.com/tcp/443/wss".parse().unwrap();
+        assert_eq!(addr.to_url().unwrap(), "wss://example.com/");
+    }
+
+    #[test]
+    fn to_url_tls() {
+        let addr: Multiaddr = "/dns4/example.com/tcp/443/tls/http".parse().unwrap();
+        assert_eq!(addr.to_url().unwrap(), "https://example.com/");
+
+        let addr: Multiaddr = "/dns6/example.com/tcp/8443/tls/ws".parse().unwrap();
+        assert_eq!(addr.to_url().unwrap(), "wss://example.com:8443/");
+
+        let addr: Multiaddr = "/dns/example.com/tcp/8443/https".parse().unwrap();
+        assert_eq!(addr.to_url().unwrap(), "https://example.com:8443/");
+    }
+
+    #[test]
+    fn to_url_http_path() {
+        let addr: Multiaddr = "/ip4/1.2.3.4/tcp/80/http/http-path/api%2Fv0%2Flogin"
+            .parse()
+            .unwrap();
+        assert_eq!(addr.to_url().unwrap(), "http://1.2.3.4/api/v0/login");
+
+        let addr: Multiaddr = "/ip4/1.2.3.4/tcp/80/http/http-path/a%2520space"
+            .parse()
+            .unwrap();
+        assert_eq!(addr.to_url().unwrap(), "http://1.2.3.4/a%2520space");
+    }
+
+    #[test]
+    fn to_url_ip6() {
+        let addr: Multiaddr = "/ip6/::1/tcp/80/ws".parse().unwrap();
+        assert_eq!(addr.to_url().unwrap(), "ws://[::1]/");
+        assert_eq!(from_url("ws://[::1]/").unwrap(), addr);
+
+        let addr: Multiaddr = "/ip6zone/eth0/ip6/fe80::1/tcp/80/http".parse().unwrap();
+        assert_eq!(addr.to_url().unwrap(), "http://[fe80::1%25eth0]/");
+
+        let addr: Multiaddr = "/ip6/fe80::1/ip6zone/eth0/tcp/80/http".parse().unwrap();
+        assert_eq!(addr.to_url().unwrap(), "http://[fe80::1%25eth0]/");
+    }
+
+    #[test]
+    fn to_url_unix() {
+        let addr = Multiaddr::from(Protocol::Unix("/foo/bar".into()));
+        assert_eq!(addr.to_url().unwrap(), "unix:/foo/bar");
+        assert_eq!(from_url(&addr.to_url().unwrap()).unwrap(), addr);
+    }
+
+    #[test]
+    fn to_url_unix_reserved_characters() {
+        for path in [
+            "/tmp/a b#c?d%41e/node.sock",
+            "run/100%/é.sock",
+            "\0abstract",
+        ] {
+            let addr = Multiaddr::from(Protocol::Unix(path.into()));
+            let url = addr.to_url().unwrap();
+            assert_eq!(from_url(&url).unwrap(), addr, "{url}");
+        }
+        assert_eq!(
+            Multiaddr::from(Protocol::Unix("/tmp/a b#c?d%41e".into()))
+                .to_url()
+                .unwrap(),
+            "unix:/tmp/a%20b%23c%3Fd%2541e"
+        );
+        assert!(from_url("unix:/tmp/%FF").is_err());
+        assert!(from_url_lossy("unix:/tmp/%FF").is_ok());
+    }
+
+    #[test]
+    fn to_url_unsupported() {
+        let addr: Multiaddr = "/ip4/1.2.3.4/udp/443/quic-v1".parse().unwrap();
+        assert_eq!(addr.to_url(), Err(ToUrlErr::UnsupportedProtocol("udp")));
+
+        let addr: Multiaddr =
+            "/ip4/1.2.3.4/tcp/443/wss/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC"
+                .parse()
+                .unwrap();
+        assert_eq!(addr.to_url(), Err(ToUrlErr::UnsupportedProtocol("p2p")));
+
+        let addr: Multiaddr = "/ip4/1.2.3.4/tcp/443".parse().unwrap();
+        assert_eq!(addr.to_url(), Err(ToUrlErr::Incomplete));
+
+        assert_eq!(Multiaddr::empty().to_url(), Err(ToUrlErr::Incomplete));
+    }
+
+    /// A multiaddress in the subset that [`from_url`] produces, including absolute and
+    /// relative `/unix` paths.
+    #[derive(Clone, Debug)]
+    struct UrlMa(Multiaddr);
+
+    impl Arbitrary for UrlMa {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let host = match u8::arbitrary(g) % 3 {
+                0 => Protocol::Ip4(Ipv4Addr::arbitrary(g)),
+                1 => Protocol::Ip6(Ipv6Addr::arbitrary(g)),
+                _ => Protocol::Dns(Cow::Owned(format!(
+                    "{}.{}",
+                    Label::arbitrary(g).0,
+                    Label::arbitrary(g).0
+                ))),
+            };
+            let path = match bool::arbitrary(g) {
+                true => None,
+                false => Some(
+                    (0..usize::arbitrary(g) % 3 + 1)
+                        .map(|_| Segment::arbitrary(g).0)
+                        .collect::<Vec<_>>()
+                        .join("/"),
+                ),
+            };
+            let ws_path = |path: Option<String>| match path {
+                None => Cow::Borrowed("/"),
+                Some(path) => Cow::Owned(format!("/{path}")),
+            };
+            if u8::arbitrary(g) % 5 == 0 {
+                let path = path.unwrap_or_else(|| Segment::arbitrary(g).0);
+                let path = match bool::arbitrary(g) {
+                    true => format!("/{path}"),
+                    false => path,
+                };
+                return UrlMa(Multiaddr::from(Protocol::Unix(path.into())));
+            }
+            let (scheme, http_path) = match u8::arbitrary(g) % 4 {
+                0 => (Protocol::Http, path),
+                1 => (Protocol::Https, path),
+                2 => (Protocol::Ws(ws_path(path)), None),
+                _ => (Protocol::Wss(ws_path(path)), None),
+            };
+            let mut addr = Multiaddr::empty()
+                .with(host)
+                .with(Protocol::Tcp(u16::arbitrary(g)))
+                .with(scheme);
+            if let Some(path) = http_path {
+                addr.push(Protocol::HttpPath(path.into()));
+            }
+            UrlMa(addr)
+        }
+    }
+
+    /// A non-empty path segment other than `.` and `..`, including characters that must be
+    /// escaped in an URL.
+    #[derive(Clone, Debug)]
+    struct Segment(String);
+
+    impl Arbitrary for Segment {
+        fn arbitrary(g: &mut Gen) -> Self {
+            const CHARS: &[char] = &[
+                'a',
+                'Z',
+                '0',
+                '.',
+                '-',
+                '_',
+                '~',
+                ' ',
+                '%',
+                '?',
+                '#',
+                '&',
+                '=',
+                ':',
+                '@',
+                '+',
+                '"',
+                '<',
+                '>',
+                '`',
+                '{',
+                '}',
+                '[',
+                ']',
+                'é',
+                '\u{1F600}',
+            ];
+            loop {
+                let len = usize::arbitrary(g) % 10 + 1;
+                let segment = (0..len)
+                    .map(|_| *g.choose(CHARS).unwrap())
+                    .collect::<String>();
+                if segment != "." && segment != ".." {
+                    return Segment(segment);
+                }
+            }
+        }
+    }
+
+    /// A non-empty lowercase ASCII label, valid both as a DNS label and as a path segment.
+    #[derive(Clone, Debug)]
+    struct Label(String);
+
+    impl Arbitrary for Label {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let len = usize::arbitrary(g) % 10 + 1;
+            Label(
+                (0..len)
+                    .map(|_| (b'a' + u8::arbitrary(g) % 26) as char)
+                    .collect(),
+            )
+        }
+    }
+
+    #[test]
+    fn to_from_url_identity() {
+        fn prop(UrlMa(addr): UrlMa) -> bool {
+            let url = addr.to_url().unwrap();
+            from_url(&url).unwrap() == addr
+        }
+        QuickCheck::new().quickcheck(prop as fn(UrlMa) -> bool)
+    }
+
+    #[test]
+    fn from_to_url_identity() {
+        fn prop(UrlMa(addr): UrlMa) -> bool {
+            let url = addr.to_url().unwrap();
+            from_url(&url).unwrap().to_url().unwrap() == url
+        }
+        QuickCheck::new().quickcheck(prop as fn(UrlMa) -> bool)
+    }
 }
@@ -0,0 +1,510 @@
+//! Random multiaddresses for property tests and fuzzing.
+//!
+//! With the `quickcheck` and `arbitrary` features, [`Multiaddr`], [`Protocol`] and
+//! [`ValidMultiaddr`] implement `quickcheck::Arbitrary` and `arbitrary::Arbitrary`. With the
+//! `proptest` feature, `any_protocol`, `any_multiaddr` and `valid_multiaddr` return
+//! proptest strategies.
+//!
+//! An arbitrary [`Protocol`] is any builtin protocol with a value that survives a round trip
+//! through the string and binary representations, and an arbitrary [`Multiaddr`] is any
+//! sequence of them. Most of these are not meaningful addresses, to generate addresses that
+//! pass [`MultiaddrSlice::validate`](crate::MultiaddrSlice::validate), such as
+//! `/ip4/<ip>/tcp/<port>/tls/p2p/<peer>` or
+//! `/ip6/<ip>/udp/<port>/quic-v1/webtransport/certhash/<hash>`, use [`ValidMultiaddr`].
+//! Protocols registered at runtime are never generated.
+//!
+//! # Example
+//!
+//! ```
+//! # #[cfg(feature = "quickcheck")]
+//! # {
+//! use multiaddr::generators::ValidMultiaddr;
+//! use quickcheck::{Arbitrary, Gen};
+//!
+//! let ValidMultiaddr(addr) = ValidMultiaddr::arbitrary(&mut Gen::new(32));
+//! assert!(addr.validate().is_ok());
+//! # }
+//! ```
+//!
+
+use crate::{Multiaddr, PeerId, Protocol};
+use alloc::{borrow::Cow, format, string::String, vec::Vec};
+use core::iter;
+
+/// The number of [`Protocol`] variants generated, all but [`Protocol::Custom`].
+const VARIANT_COUNT: usize = 45;
+
+const MULTIHASH_SHA256_CODE: u64 = 0x12;
+
+/// A multiaddress that passes [`MultiaddrSlice::validate`](crate::MultiaddrSlice::validate),
+/// for use as an arbitrary value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ValidMultiaddr(pub Multiaddr);
+
+/// A source of randomness, implemented by the generators of each supported framework.
+trait Source {
+    fn u8(&mut self) -> u8;
+    fn u16(&mut self) -> u16;
+    fn u64(&mut self) -> u64;
+
+    /// Returns a number in `0..n`, `n` must not be zero.
+    fn below(&mut self, n: usize) -> usize;
+
+    fn bool(&mut self) -> bool {
+        self.u8() & 1 == 1
+    }
+
+    fn bytes(&mut self, len: usize) -> Vec<u8> {
+        iter::repeat_with(|| self.u8()).take(len).collect()
+    }
+}
+
+fn random_protocol<S: Source>(s: &mut S) -> Protocol<'static> {
+    use Protocol::*;
+    match s.below(VARIANT_COUNT) {
+        0 => Dccp(s.u16()),
+        1 => Dns(Cow::Owned(substring(s))),
+        2 => Dns4(Cow::Owned(substring(s))),
+        3 => Dns6(Cow::Owned(substring(s))),
+        4 => Dnsaddr(Cow::Owned(substring(s))),
+        5 => Http,
+        6 => Https,
+        7 => random_ip4(s),
+        8 => random_ip6(s),
+        9 => P2pWebRtcDirect,
+        10 => P2pWebRtcStar,
+        11 => WebRTCDirect,
+        12 => Certhash(multihash(s)),
+        13 => P2pWebSocketStar,
+        14 => Memory(s.u64()),
+        15 => {
+            let hash = s.bytes(10).try_into().expect("10 bytes");
+            Onion(Cow::Owned(hash), s.u16().max(1))
+        }
+        16 => {
+            let hash: [u8; 35] = s.bytes(35).try_into().expect("35 bytes");
+            Onion3((hash, s.u16().max(1)).into())
+        }
+        17 => P2p(peer_id(s)),
+        18 => P2pCircuit,
+        19 => Quic,
+        20 => QuicV1,
+        21 => Sctp(s.u16()),
+        22 => Tcp(s.u16()),
+        23 => Tls,
+        24 => Noise,
+        25 => Udp(s.u16()),
+        26 => Udt,
+        27 => Unix(Cow::Owned(unix_path(s))),
+        28 => Utp,
+        29 => WebTransport,
+        30 => Ws("/".into()),
+        31 => Wss("/".into()),
+        32 => Ip6zone(Cow::Owned(substring(s))),
+        33 => Ipcidr(s.u8()),
+        34 => {
+            let len = s.below(462 - 387) + 387;
+            Garlic64(Cow::Owned(s.bytes(len)))
+        }
+        35 => {
+            let len = if s.bool() { 32 } else { s.below(128) + 35 };
+            Garlic32(Cow::Owned(s.bytes(len)))
+        }
+        36 => Sni(Cow::Owned(substring(s))),
+        37 => P2pStardust,
+        38 => WebRTC,
+        39 => HttpPath(Cow::Owned(substring(s))),
+        40 => {
+            let len = s.below(64);
+            Thread(Cow::Owned(s.bytes(len)))
+        }
+        41 => Shs(Cow::Owned(s.bytes(32))),
+        42 => PlaintextV2,
+        43 => Scion(Cow::Owned(substring(s))),
+        44 => Silverpine,
+        _ => unreachable!("below the variant count"),
+    }
+}
+
+fn random_multiaddr<S: Source>(s: &mut S) -> Multiaddr {
+    let len = s.below(128);
+    (0..len).map(|_| random_protocol(s)).collect()
+}
+
+fn random_valid_multiaddr<S: Source>(s: &mut S) -> Multiaddr {
+    use Protocol::*;
+    let mut addr = Multiaddr::empty();
+    match s.below(8) {
+        0 => addr.push(Memory(s.u64())),
+        1 => addr.push(Unix(Cow::Owned(format!("/tmp/{}.sock", name(s))))),
+        2 => addr.push(Dnsaddr(Cow::Owned(name(s)))),
+        _ => {
+            host(s, &mut addr);
+            transport(s, &mut addr);
+        }
+    }
+    if s.bool() {
+        addr.push(P2p(peer_id(s)));
+        if s.below(4) == 0 {
+            addr.push(P2pCircuit);
+            if s.bool() {
+                addr.push(WebRTC);
+            }
+            if s.bool() {
+                addr.push(P2p(peer_id(s)));
+            }
+        }
+    }
+    addr
+}
+
+fn host<S: Source>(s: &mut S, addr: &mut Multiaddr) {
+    use Protocol::*;
+    match s.below(6) {
+        0 | 1 => addr.push(random_ip4(s)),
+        2 => addr.push(random_ip6(s)),
+        3 => {
+            addr.push(Ip6zone(Cow::Owned(name(s))));
+            addr.push(random_ip6(s));
+        }
+        4 => addr.push(Dns(Cow::Owned(name(s)))),
+        _ => match s.bool() {
+            true => addr.push(Dns4(Cow::Owned(name(s)))),
+            false => addr.push(Dns6(Cow::Owned(name(s)))),
+        },
+    }
+}
+
+fn transport<S: Source>(s: &mut S, addr: &mut Multiaddr) {
+    use Protocol::*;
+    if s.bool() {
+        addr.push(Tcp(s.u16()));
+        match s.below(10) {
+            0 => {}
+            1 => addr.push(Tls),
+            2 => {
+                addr.push(Tls);
+                addr.push(Sni(Cow::Owned(name(s))));
+            }
+            3 => {
+                addr.push(Tls);
+                addr.push(Http);
+            }
+            4 => {
+                addr.push(Http);
+                addr.push(HttpPath(Cow::Owned(name(s))));
+            }
+            5 => addr.push(Https),
+            6 => addr.push(Ws("/".into())),
+            7 => {
+                addr.push(Tls);
+                addr.push(Ws("/".into()));
+            }
+            8 => addr.push(Wss("/".into())),
+            _ => addr.push(Noise),
+        }
+    } else {
+        addr.push(Udp(s.u16()));
+        match s.below(6) {
+            0 => addr.push(QuicV1),
+            1 => {
+                addr.push(QuicV1);
+                addr.push(WebTransport);
+                for _ in 0..s.below(3) {
+                    addr.push(Certhash(multihash(s)));
+                }
+            }
+            2 => addr.push(Quic),
+            3 => {
+                addr.push(WebRTCDirect);
+                for _ in 0..=s.below(2) {
+                    addr.push(Certhash(multihash(s)));
+                }
+            }
+            4 => addr.push(Utp),
+            _ => addr.push(Udt),
+        }
+    }
+}
+
+fn random_ip4<S: Source>(s: &mut S) -> Protocol<'static> {
+    Protocol::Ip4((s.u64() as u32).into())
+}
+
+fn random_ip6<S: Source>(s: &mut S) -> Protocol<'static> {
+    Protocol::Ip6((u128::from(s.u64()) << 64 | u128::from(s.u64())).into())
+}
+
+fn multihash<S: Source>(s: &mut S) -> multihash::Multihash<64> {
+    multihash::Multihash::wrap(MULTIHASH_SHA256_CODE, &s.bytes(32)).expect("digest fits")
+}
+
+fn peer_id<S: Source>(s: &mut S) -> PeerId {
+    PeerId::from_multihash(multihash(s)).expect("sha2-256 multihashes are peer ids")
+}
+
+/// An ASCII string without `/`.
+fn substring<S: Source>(s: &mut S) -> String {
+    let len = s.below(32);
+    (0..len)
+        .map(|_| char::from(s.u8() % 128))
+        .filter(|c| *c != '/')
+        .collect()
+}
+
+/// A non-empty path of a Unix domain socket, absolute, relative or abstract.
+fn unix_path<S: Source>(s: &mut S) -> String {
+    let prefix = match s.below(3) {
+        0 => "/",
+        1 => "\0",
+        _ => "",
+    };
+    let segments = (0..=s.below(3)).map(|_| substring(s)).collect::<Vec<_>>();
+    let path = format!("{prefix}{}", segments.join("/"));
+    if path.is_empty() {
+        String::from("/")
+    } else {
+        path
+    }
+}
+
+/// A non-empty domain name.
+fn name<S: Source>(s: &mut S) -> String {
+    const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+    let labels = s.below(3) + 1;
+    let mut name = String::new();
+    for i in 0..labels {
+        if i > 0 {
+            name.push('.');
+        }
+        for _ in 0..=s.below(8) {
+            name.push(char::from(ALPHABET[s.below(ALPHABET.len())]));
+        }
+    }
+    name
+}
+
+/// The prefixes of `addr`, from longest to shortest, that are valid if `valid` is set.
+#[cfg(any(feature = "quickcheck", feature = "proptest"))]
+fn prefixes(addr: &Multiaddr, valid: bool) -> impl Iterator<Item = Multiaddr> {
+    let components = addr.iter().map(Protocol::acquire).collect::<Vec<_>>();
+    (0..components.len())
+        .rev()
+        .map(move |n| components[..n].iter().cloned().collect::<Multiaddr>())
+        .filter(move |prefix| !valid || prefix.validate().is_ok())
+}
+
+#[cfg(feature = "quickcheck")]
+mod quickcheck_impls {
+    use super::*;
+    use quickcheck::{Arbitrary, Gen};
+
+    impl Source for Gen {
+        fn u8(&mut self) -> u8 {
+            u8::arbitrary(self)
+        }
+
+        fn u16(&mut self) -> u16 {
+            u16::arbitrary(self)
+        }
+
+        fn u64(&mut self) -> u64 {
+            u64::arbitrary(self)
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            usize::arbitrary(self) % n
+        }
+    }
+
+    impl Arbitrary for Protocol<'static> {
+        fn arbitrary(g: &mut Gen) -> Self {
+            random_protocol(g)
+        }
+    }
+
+    impl Arbitrary for Multiaddr {
+        fn arbitrary(g: &mut Gen) -> Self {
+            random_multiaddr(g)
+        }
+
+        fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+            Box::new(prefixes(self, false))
+        }
+    }
+
+    impl Arbitrary for ValidMultiaddr {
+        fn arbitrary(g: &mut Gen) -> Self {
+            ValidMultiaddr(random_valid_multiaddr(g))
+        }
+
+        fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+            Box::new(prefixes(&self.0, true).map(ValidMultiaddr))
+        }
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impls {
+    use super::*;
+    use arbitrary::{Arbitrary, Result, Unstructured};
+
+    impl Source for Unstructured<'_> {
+        fn u8(&mut self) -> u8 {
+            u8::arbitrary(self).unwrap_or_default()
+        }
+
+        fn u16(&mut self) -> u16 {
+            u16::arbitrary(self).unwrap_or_default()
+        }
+
+        fn u64(&mut self) -> u64 {
+            u64::arbitrary(self).unwrap_or_default()
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            self.int_in_range(0..=n - 1).unwrap_or_default()
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for Protocol<'static> {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            Ok(random_protocol(u))
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for Multiaddr {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            Ok(random_multiaddr(u))
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for ValidMultiaddr {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            Ok(ValidMultiaddr(random_valid_multiaddr(u)))
+        }
+    }
+}
+
+#[cfg(feature = "proptest")]
+pub use self::proptest_impls::{any_multiaddr, any_protocol, valid_multiaddr};
+
+#[cfg(feature = "proptest")]
+mod proptest_impls {
+    use super::*;
+    use proptest::{
+        prelude::Rng,
+        strategy::{Just, NewTree, Strategy, ValueTree},
+        test_runner::{TestRng, TestRunner},
+    };
+
+    impl Source for TestRng {
+        fn u8(&mut self) -> u8 {
+            self.next_u32() as u8
+        }
+
+        fn u16(&mut self) -> u16 {
+            self.next_u32() as u16
+        }
+
+        fn u64(&mut self) -> u64 {
+            self.next_u64()
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next_u64() % n as u64) as usize
+        }
+    }
+
+    /// Returns a strategy for arbitrary protocols, which do not shrink.
+    pub fn any_protocol() -> impl Strategy<Value = Protocol<'static>> {
+        ProtocolStrategy
+    }
+
+    /// Returns a strategy for arbitrary multiaddresses, which shrink to their prefixes.
+    pub fn any_multiaddr() -> impl Strategy<Value = Multiaddr> {
+        MultiaddrStrategy { valid: false }
+    }
+
+    /// Returns a strategy for multiaddresses that pass
+    /// [`MultiaddrSlice::validate`](crate::MultiaddrSlice::validate), which shrink to their
+    /// valid prefixes.
+    pub fn valid_multiaddr() -> impl Strategy<Value = Multiaddr> {
+        MultiaddrStrategy { valid: true }
+    }
+
+    #[derive(Debug)]
+    struct ProtocolStrategy;
+
+    impl Strategy for ProtocolStrategy {
+        type Tree = Just<Protocol<'static>>;
+        type Value = Protocol<'static>;
+
+        fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+            Ok(Just(random_protocol(runner.rng())))
+        }
+    }
+
+    #[derive(Debug)]
+    struct MultiaddrStrategy {
+        valid: bool,
+    }
+
+    impl Strategy for MultiaddrStrategy {
+        type Tree = MultiaddrTree;
+        type Value = Multiaddr;
+
+        fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+            let addr = match self.valid {
+                true => random_valid_multiaddr(runner.rng()),
+                false => random_multiaddr(runner.rng()),
+            };
+            let mut prefixes = prefixes(&addr, self.valid).collect::<Vec<_>>();
+            prefixes.reverse();
+            Ok(MultiaddrTree {
+                current: addr,
+                prefixes,
+                previous: None,
+            })
+        }
+    }
+
+    /// Shrinks a multiaddress by trying its prefixes, from longest to shortest.
+    #[derive(Debug)]
+    struct MultiaddrTree {
+        current: Multiaddr,
+        /// The prefixes of `current` left to try, from shortest to longest.
+        prefixes: Vec<Multiaddr>,
+        previous: Option<Multiaddr>,
+    }
+
+    impl ValueTree for MultiaddrTree {
+        type Value = Multiaddr;
+
+        fn current(&self) -> Multiaddr {
+            self.current.clone()
+        }
+
+        fn simplify(&mut self) -> bool {
+            match self.prefixes.pop() {
+                Some(prefix) => {
+                    self.previous = Some(core::mem::replace(&mut self.current, prefix));
+                    true
+                }
+                None => false,
+            }
+        }
+
+        fn complicate(&mut self) -> bool {
+            // The simplified value passed, so the previous one is the shortest failing prefix.
+            match self.previous.take() {
+                Some(previous) => {
+                    self.current = previous;
+                    self.prefixes.clear();
+                    true
+                }
+                None => false,
+            }
+        }
+    }
+}
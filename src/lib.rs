@@ -1,41 +1,107 @@
 //! Implementation of [multiaddr](https://github.com/multiformats/multiaddr) in Rust.
+//!
+//! # Features
+//!
+//! - `std` (default): implements [`Writer`] for every [`std::io::Write`] and enables the
+//!   [protocol registry](register_protocol), the predefined [`patterns`],
+//!   [resolving](MultiaddrSlice::resolve) `/dns*` components and conversions from and to
+//!   [Unix socket paths](Multiaddr::from_unix_path). Without it, the crate only
+//!   requires `alloc`.
+//! - `serde` (default): `Serialize` and `Deserialize` for [`Multiaddr`], [`MultiaddrSlice`]
+//!   and [`MultiaddrFilter`].
+//! - `url` (default): conversions from and to URLs.
+//! - `tokio-util`, `asynchronous-codec`: `MultiaddrCodec` for framing streams of
+//!   [length-prefixed](MultiaddrSlice::write_length_prefixed) multiaddresses.
+//! - `quickcheck`, `arbitrary`, `proptest`: random multiaddresses in the `generators` module.
+//! - `libp2p-identity`: conversions between [`PeerId`] and `libp2p_identity::PeerId`.
+#![cfg_attr(not(feature = "std"), no_std)]
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 
+extern crate alloc;
+
 pub use multihash;
 
+pub mod dnsaddr;
 mod errors;
+mod filter;
+#[cfg(feature = "std")]
+mod framing;
+#[cfg(any(feature = "arbitrary", feature = "proptest", feature = "quickcheck"))]
+pub mod generators;
+mod normalize;
 mod onion_addr;
+mod pattern;
+#[cfg(feature = "std")]
+pub mod patterns;
+mod peer_id;
 mod protocol;
+mod registry;
+mod relay;
+#[cfg(feature = "std")]
+mod resolve;
+mod scope;
+mod slice;
+mod socket_addr;
+mod transport;
+#[cfg(feature = "std")]
+mod unix;
+mod validate;
+mod ws_path;
 
 #[cfg(feature = "url")]
 mod from_url;
 
 pub use self::errors::{Error, Result};
+pub use self::filter::{Action, FilterError, MultiaddrFilter};
+#[cfg(any(feature = "tokio-util", feature = "asynchronous-codec"))]
+pub use self::framing::MultiaddrCodec;
+#[cfg(feature = "std")]
+pub use self::framing::{FramingError, DEFAULT_MAX_FRAME_LEN};
 pub use self::onion_addr::Onion3Addr;
-pub use self::protocol::Protocol;
-use bytes::{BufMut, Bytes, BytesMut};
-use serde::{
-    de::{self, Error as DeserializerError},
-    Deserialize, Deserializer, Serialize, Serializer,
+pub use self::pattern::{Captures, Pattern, PatternError};
+pub use self::peer_id::{ParsePeerIdError, PeerId};
+pub use self::protocol::{Layer, Protocol, ProtocolKind, ValueSize, Writer};
+#[cfg(feature = "std")]
+pub use self::registry::register_protocol;
+pub use self::registry::{CustomProtocol, RegisterError, ValueCodec};
+pub use self::relay::{RelayedAddr, RelayedAddrBuilder, RelayedAddrErr};
+#[cfg(feature = "std")]
+pub use self::resolve::{
+    AsyncResolver, BoxFuture, MockResolver, ResolveError, Resolver, MAX_DNSADDR_DEPTH, MAX_LOOKUPS,
+    MAX_RESOLVED_ADDRS,
 };
-use std::{
+pub use self::scope::Scope;
+pub use self::slice::MultiaddrSlice;
+pub use self::socket_addr::{SocketAddrErr, Transport};
+pub use self::transport::TransportKind;
+#[cfg(feature = "std")]
+pub use self::unix::UnixAddrErr;
+pub use self::validate::{Rule, StrictParseError, Violation};
+pub use self::ws_path::WsPathEncoding;
+use alloc::{string::String, vec::Vec};
+use bytes::Bytes;
+use core::{
     convert::TryFrom,
     fmt,
     iter::FromIterator,
+    mem,
     net::{IpAddr, Ipv4Addr, Ipv6Addr},
     result::Result as StdResult,
     str::FromStr,
 };
-
-pub use libp2p_identity::PeerId;
+#[cfg(feature = "serde")]
+use serde::{
+    de::{self, Error as DeserializerError},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 
 #[cfg(feature = "url")]
-pub use self::from_url::{from_url, from_url_lossy, FromUrlErr};
+pub use self::from_url::{from_url, from_url_lossy, FromUrlErr, ToUrlErr};
 
 static_assertions::const_assert! {
     // This check is most certainly overkill right now, but done here
     // anyway to ensure the `as u64` casts in this crate are safe.
-    std::mem::size_of::<usize>() <= std::mem::size_of::<u64>()
+    core::mem::size_of::<usize>() <= core::mem::size_of::<u64>()
 }
 
 /// Representation of a Multiaddr.
@@ -56,27 +122,35 @@ impl Multiaddr {
     /// Create a new, empty multiaddress with the given capacity.
     pub fn with_capacity(n: usize) -> Self {
         Self {
-            bytes: BytesMut::with_capacity(n).freeze(),
+            bytes: Bytes::from(Vec::with_capacity(n)),
         }
     }
 
-    /// Return the length in bytes of this multiaddress.
-    pub fn len(&self) -> usize {
-        self.bytes.len()
-    }
-
-    /// Returns true if the length of this multiaddress is 0.
-    pub fn is_empty(&self) -> bool {
-        self.bytes.len() == 0
+    /// Creates a multiaddress from the binary representation of valid components, for the
+    /// `ma!` macro of `multiaddr-macros`.
+    #[doc(hidden)]
+    pub const fn __from_static(bytes: &'static [u8]) -> Self {
+        Self {
+            bytes: Bytes::from_static(bytes),
+        }
     }
 
-    /// Return a copy of this [`Multiaddr`]'s byte representation.
-    pub fn to_vec(&self) -> Vec<u8> {
-        Vec::from(&self.bytes[..])
+    /// Appends the binary representation of valid components, for the `ma!` macro of
+    /// `multiaddr-macros`.
+    #[doc(hidden)]
+    pub fn __push_encoded(&mut self, encoded: &[u8]) {
+        let mut bytes = Vec::from(mem::take(&mut self.bytes));
+        bytes.extend_from_slice(encoded);
+        self.bytes = Bytes::from(bytes);
     }
 
     /// Adds an already-parsed address component to the end of this multiaddr.
     ///
+    /// # Panics
+    ///
+    /// Panics if `p` is a [`Protocol::Custom`] that cannot be written, see
+    /// [`Multiaddr::try_push`].
+    ///
     /// # Examples
     ///
     /// ```
@@ -88,10 +162,22 @@ impl Multiaddr {
     /// ```
     ///
     pub fn push(&mut self, p: Protocol<'_>) {
-        let mut bytes = BytesMut::from(std::mem::take(&mut self.bytes));
-        p.write_bytes(&mut (&mut bytes).writer())
-            .expect("Writing to a `BytesMut` never fails.");
-        self.bytes = bytes.freeze();
+        self.try_push(p)
+            .expect("`Protocol::Custom` is registered and its value matches its codec.");
+    }
+
+    /// Like [`Multiaddr::push`], but fails instead of panicking if `p` is a
+    /// [`Protocol::Custom`] whose code is not registered, or whose value does not match the
+    /// registered [`ValueCodec`]. This multiaddr is left unchanged on failure.
+    pub fn try_push(&mut self, p: Protocol<'_>) -> Result<()> {
+        let mut bytes = Vec::from(mem::take(&mut self.bytes));
+        let len = bytes.len();
+        let result = p.write_bytes(&mut bytes);
+        if result.is_err() {
+            bytes.truncate(len);
+        }
+        self.bytes = Bytes::from(bytes);
+        result
     }
 
     /// Pops the last `Protocol` of this multiaddr, or `None` if the multiaddr is empty.
@@ -117,26 +203,35 @@ impl Multiaddr {
             slice = s
         };
         let remaining_len = self.len() - slice.len();
-        let mut bytes = BytesMut::from(std::mem::take(&mut self.bytes));
-        bytes.truncate(remaining_len);
-        self.bytes = bytes.freeze();
+        self.bytes.truncate(remaining_len);
         Some(protocol)
     }
 
     /// Like [`Multiaddr::push`] but consumes `self`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p` is a [`Protocol::Custom`] that cannot be written, see
+    /// [`Multiaddr::try_with`].
     pub fn with(mut self, p: Protocol<'_>) -> Self {
-        let mut bytes = BytesMut::from(std::mem::take(&mut self.bytes));
-        p.write_bytes(&mut (&mut bytes).writer())
-            .expect("Writing to a `BytesMut` never fails.");
-        self.bytes = bytes.freeze();
+        self.push(p);
         self
     }
 
+    /// Like [`Multiaddr::try_push`] but consumes `self`.
+    pub fn try_with(mut self, p: Protocol<'_>) -> Result<Self> {
+        self.try_push(p)?;
+        Ok(self)
+    }
+
     /// Appends the given [`PeerId`] if not yet present at the end of this multiaddress.
     ///
     /// Fails if this address ends in a _different_ [`PeerId`].
     /// In that case, the original, unmodified address is returned.
-    pub fn with_p2p(self, peer: PeerId) -> std::result::Result<Self, Self> {
+    ///
+    /// With the `libp2p-identity` feature, this also accepts a `libp2p_identity::PeerId`.
+    pub fn with_p2p(self, peer: impl Into<PeerId>) -> StdResult<Self, Self> {
+        let peer = peer.into();
         match self.iter().last() {
             Some(Protocol::P2p(p)) if p == peer => Ok(self),
             Some(Protocol::P2p(_)) => Err(self),
@@ -144,24 +239,54 @@ impl Multiaddr {
         }
     }
 
-    /// Returns the components of this multiaddress.
+    /// Decodes as much of the given binary multiaddress as possible.
+    ///
+    /// Unlike `TryFrom<Vec<u8>>`, which fails if any component is invalid, this returns the
+    /// longest valid prefix of `bytes`, together with a description of the undecodable
+    /// remainder, if any. This keeps the known parts of an address that contains protocols
+    /// this crate doesn't know.
     ///
     /// # Example
     ///
-    /// ```rust
-    /// use std::net::Ipv4Addr;
-    /// use multiaddr::{Multiaddr, Protocol};
+    /// ```
+    /// use multiaddr::Multiaddr;
     ///
-    /// let address: Multiaddr = "/ip4/127.0.0.1/udt/sctp/5678".parse().unwrap();
+    /// let mut bytes = "/ip4/127.0.0.1/udp/1234".parse::<Multiaddr>().unwrap().to_vec();
+    /// bytes.extend([0xff, 0xff, 0x03, 0x01, 0x02]);
     ///
-    /// let components = address.iter().collect::<Vec<_>>();
-    /// assert_eq!(components[0], Protocol::Ip4(Ipv4Addr::new(127, 0, 0, 1)));
-    /// assert_eq!(components[1], Protocol::Udt);
-    /// assert_eq!(components[2], Protocol::Sctp(5678));
+    /// let (address, tail) = Multiaddr::decode_lenient(&bytes);
+    /// assert_eq!(address, "/ip4/127.0.0.1/udp/1234".parse().unwrap());
+    ///
+    /// let tail = tail.unwrap();
+    /// assert_eq!(tail.offset, 9);
+    /// assert_eq!(tail.code, Some(0xffff));
+    /// assert_eq!(tail.remainder, &[0xff, 0xff, 0x03, 0x01, 0x02]);
     /// ```
     ///
-    pub fn iter(&self) -> Iter<'_> {
-        Iter(&self.bytes)
+    pub fn decode_lenient(bytes: &[u8]) -> (Multiaddr, Option<UndecodedTail<'_>>) {
+        let mut slice = bytes;
+        while !slice.is_empty() {
+            match Protocol::from_bytes(slice) {
+                Ok((_, rest)) => slice = rest,
+                Err(error) => {
+                    let offset = bytes.len() - slice.len();
+                    let tail = UndecodedTail {
+                        offset,
+                        code: unsigned_varint::decode::u32(slice).ok().map(|(c, _)| c),
+                        remainder: slice,
+                        error,
+                    };
+                    let address = Multiaddr {
+                        bytes: Bytes::copy_from_slice(&bytes[..offset]),
+                    };
+                    return (address, Some(tail));
+                }
+            }
+        }
+        let address = Multiaddr {
+            bytes: Bytes::copy_from_slice(bytes),
+        };
+        (address, None)
     }
 
     /// Replace a [`Protocol`] at some position in this `Multiaddr`.
@@ -200,31 +325,32 @@ impl Multiaddr {
         }
     }
 
-    /// Checks whether the given `Multiaddr` is a suffix of this `Multiaddr`.
-    pub fn ends_with(&self, other: &Multiaddr) -> bool {
-        let n = self.bytes.len();
-        let m = other.bytes.len();
-        if n < m {
-            return false;
-        }
-        self.bytes[(n - m)..] == other.bytes[..]
-    }
-
-    /// Checks whether the given `Multiaddr` is a prefix of this `Multiaddr`.
-    pub fn starts_with(&self, other: &Multiaddr) -> bool {
-        let n = self.bytes.len();
-        let m = other.bytes.len();
-        if n < m {
-            return false;
-        }
-        self.bytes[..m] == other.bytes[..]
-    }
-
-    /// Returns &str identifiers for the protocol names themselves.
-    /// This omits specific info like addresses, ports, peer IDs, and the like.
-    /// Example: `"/ip4/127.0.0.1/tcp/5001"` would return `["ip4", "tcp"]`
-    pub fn protocol_stack(&self) -> ProtoStackIter {
-        ProtoStackIter { parts: self.iter() }
+    /// Attempts to turn this multiaddress into an URL.
+    ///
+    /// This is the inverse of [`from_url`]. The address must consist of a host (`/ip4`,
+    /// `/ip6`, optionally with `/ip6zone`, or one of the `/dns` variants), a `/tcp` port and
+    /// one of `/http`, `/tls/http`, `/https`, `/ws`, `/tls/ws` or `/wss`. An `/http-path`
+    /// may follow `/http` or a `/ws` without a path. Paths are percent-encoded as needed.
+    /// A single `/unix` component is turned into a `unix:` URL.
+    ///
+    /// This function is only present if the `url` feature is enabled, and it is
+    /// enabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use multiaddr::Multiaddr;
+    ///
+    /// let addr: Multiaddr = "/dns/example.com/tcp/8080/tls/ws".parse().unwrap();
+    /// assert_eq!(addr.to_url().unwrap(), "wss://example.com:8080/");
+    ///
+    /// let addr: Multiaddr = "/ip4/127.0.0.1/udp/443/quic-v1".parse().unwrap();
+    /// assert!(addr.to_url().is_err());
+    /// ```
+    ///
+    #[cfg(feature = "url")]
+    pub fn to_url(&self) -> StdResult<String, ToUrlErr> {
+        from_url::to_url(self)
     }
 }
 
@@ -247,10 +373,7 @@ impl fmt::Display for Multiaddr {
     /// ```
     ///
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for s in self.iter() {
-            s.fmt(f)?;
-        }
-        Ok(())
+        fmt::Display::fmt(&**self, f)
     }
 }
 
@@ -270,18 +393,19 @@ impl<'a> IntoIterator for &'a Multiaddr {
 }
 
 impl<'a> FromIterator<Protocol<'a>> for Multiaddr {
+    /// Collects the components into a multiaddr.
+    ///
+    /// Panics if a component is a [`Protocol::Custom`] that cannot be written, see
+    /// [`Multiaddr::try_push`].
     fn from_iter<T>(iter: T) -> Self
     where
         T: IntoIterator<Item = Protocol<'a>>,
     {
-        let mut bytes = BytesMut::new();
+        let mut addr = Multiaddr::empty();
         for cmp in iter {
-            cmp.write_bytes(&mut (&mut bytes).writer())
-                .expect("Writing to a `BytesMut` never fails.");
-        }
-        Multiaddr {
-            bytes: bytes.freeze(),
+            addr.push(cmp);
         }
+        addr
     }
 }
 
@@ -289,26 +413,54 @@ impl FromStr for Multiaddr {
     type Err = Error;
 
     fn from_str(input: &str) -> Result<Self> {
-        let mut bytes = BytesMut::new();
-        let mut parts = input.split('/').peekable();
+        let mut bytes = Vec::new();
+        // The parts of the input, together with their byte offsets.
+        let mut parts = input
+            .split('/')
+            .scan(0, |offset, part| {
+                let start = *offset;
+                *offset += part.len() + 1;
+                Some((start, part))
+            })
+            .peekable();
 
-        if Some("") != parts.next() {
+        if Some("") != parts.next().map(|(_, part)| part) {
             // A multiaddr must start with `/`
             return Err(Error::InvalidMultiaddr);
         }
 
+        let mut index = 0;
         while parts.peek().is_some() {
-            let p = Protocol::from_str_parts(&mut parts)?;
-            p.write_bytes(&mut (&mut bytes).writer())
-                .expect("Writing to a `BytesMut` never fails.");
+            let mut consumed = Vec::new();
+            let p = Protocol::from_str_parts((&mut parts).map(|(offset, part)| {
+                consumed.push((offset, part));
+                part
+            }))
+            .map_err(|e| Error::in_string(input, index, &consumed, e))?;
+            p.write_bytes(&mut bytes)
+                .expect("Writing to a `Vec` never fails.");
+            index += 1;
         }
 
         Ok(Multiaddr {
-            bytes: bytes.freeze(),
+            bytes: Bytes::from(bytes),
         })
     }
 }
 
+/// The part of a binary multiaddress that [`Multiaddr::decode_lenient`] could not decode.
+#[derive(Debug)]
+pub struct UndecodedTail<'a> {
+    /// Byte offset of the first undecodable component.
+    pub offset: usize,
+    /// Protocol code of the first undecodable component, if it could be read.
+    pub code: Option<u32>,
+    /// The undecoded bytes, starting with the first undecodable component.
+    pub remainder: &'a [u8],
+    /// Why the component could not be decoded.
+    pub error: Error,
+}
+
 /// Iterator over `Multiaddr` [`Protocol`]s.
 pub struct Iter<'a>(&'a [u8]);
 
@@ -341,13 +493,12 @@ impl Iterator for ProtoStackIter<'_> {
 }
 
 impl<'a> From<Protocol<'a>> for Multiaddr {
+    /// Creates a multiaddr consisting of a single component.
+    ///
+    /// Panics if `p` is a [`Protocol::Custom`] that cannot be written, see
+    /// [`Multiaddr::try_with`].
     fn from(p: Protocol<'a>) -> Multiaddr {
-        let mut bytes = BytesMut::new();
-        p.write_bytes(&mut (&mut bytes).writer())
-            .expect("Writing to a `BytesMut` never fails.");
-        Multiaddr {
-            bytes: bytes.freeze(),
-        }
+        Multiaddr::empty().with(p)
     }
 }
 
@@ -376,18 +527,23 @@ impl TryFrom<Vec<u8>> for Multiaddr {
     type Error = Error;
 
     fn try_from(v: Vec<u8>) -> Result<Self> {
-        // Check if the argument is a valid `Multiaddr` by reading its protocols.
-        let mut slice = &v[..];
-        while !slice.is_empty() {
-            let (_, s) = Protocol::from_bytes(slice)?;
-            slice = s
-        }
+        slice::validate(&v)?;
         Ok(Multiaddr {
             bytes: Bytes::from(v),
         })
     }
 }
 
+impl TryFrom<Bytes> for Multiaddr {
+    type Error = Error;
+
+    /// Validates the given bytes and uses them as a multiaddress, without copying.
+    fn try_from(bytes: Bytes) -> Result<Self> {
+        slice::validate(&bytes)?;
+        Ok(Multiaddr { bytes })
+    }
+}
+
 impl TryFrom<String> for Multiaddr {
     type Error = Error;
 
@@ -404,19 +560,21 @@ impl<'a> TryFrom<&'a str> for Multiaddr {
     }
 }
 
+#[cfg(feature = "serde")]
 impl Serialize for Multiaddr {
     fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
     where
         S: Serializer,
     {
         if serializer.is_human_readable() {
-            serializer.serialize_str(&self.to_string())
+            serializer.collect_str(self)
         } else {
             serializer.serialize_bytes(self.as_ref())
         }
     }
 }
 
+#[cfg(feature = "serde")]
 impl<'de> Deserialize<'de> for Multiaddr {
     fn deserialize<D>(deserializer: D) -> StdResult<Self, D::Error>
     where
@@ -437,7 +595,7 @@ impl<'de> Deserialize<'de> for Multiaddr {
                 mut seq: A,
             ) -> StdResult<Self::Value, A::Error> {
                 let mut buf: Vec<u8> =
-                    Vec::with_capacity(std::cmp::min(seq.size_hint().unwrap_or(0), 4096));
+                    Vec::with_capacity(core::cmp::min(seq.size_hint().unwrap_or(0), 4096));
                 while let Some(e) = seq.next_element()? {
                     buf.push(e);
                 }
@@ -493,11 +651,14 @@ impl<'de> Deserialize<'de> for Multiaddr {
 /// optional parameter is turned into the proper type with the `Into` trait.
 ///
 /// For example, `Ip4([127, 0, 0, 1])` works because `Ipv4Addr` implements `From<[u8; 4]>`.
+///
+/// To write a multiaddress as a string literal that is parsed at compile time, use the `ma!`
+/// macro of the `multiaddr-macros` crate.
 #[macro_export]
 macro_rules! multiaddr {
     ($($comp:ident $(($param:expr))*),+) => {
         {
-            use std::iter;
+            use ::core::iter;
             let elem = iter::empty::<$crate::Protocol>();
             $(
                 let elem = {
@@ -0,0 +1,118 @@
+use crate::{ws_path, Multiaddr, MultiaddrSlice, Protocol};
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+
+impl MultiaddrSlice {
+    /// Returns the canonical form of this multiaddress.
+    ///
+    /// Equivalent multiaddresses have the same canonical form. The following rules are
+    /// applied to each component:
+    ///
+    /// - The deprecated `/https` becomes `/tls/http`.
+    /// - The deprecated `/wss` becomes `/tls/ws`.
+    /// - The non-standard `/x-parity-ws/<path>` and `/x-parity-wss/<path>` become
+    ///   `/ws/http-path/<path>` and `/tls/ws/http-path/<path>` respectively, with the leading
+    ///   `/` of the path implied by `/http-path`.
+    /// - IPv4-mapped IPv6 addresses without a zone, i.e. `/ip6/::ffff:<ipv4>`, become
+    ///   `/ip4/<ipv4>`. A following `/ipcidr/<prefix>` becomes `/ipcidr/<prefix - 96>`, and the
+    ///   address is kept if the prefix is shorter than 96.
+    /// - An `/ip6zone` following its `/ip6` is moved in front of it, unless it is followed by
+    ///   another `/ip6` it belongs to.
+    /// - The names of `/dns`, `/dns4`, `/dns6`, `/dnsaddr` and `/sni` are lowercased and trailing
+    ///   dots are removed.
+    ///
+    /// `/ipfs` and `/p2p` share their binary encoding, so they need no normalization.
+    /// Applying `normalize` to its own output returns the same address.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use multiaddr::Multiaddr;
+    ///
+    /// let addr: Multiaddr = "/dns/Example.COM./tcp/443/wss".parse().unwrap();
+    /// assert_eq!(addr.normalize().to_string(), "/dns/example.com/tcp/443/tls/ws");
+    ///
+    /// let addr: Multiaddr = "/ip6/::ffff:1.2.3.4/tcp/80".parse().unwrap();
+    /// assert!(addr.is_equivalent(&"/ip4/1.2.3.4/tcp/80".parse::<Multiaddr>().unwrap()));
+    /// ```
+    ///
+    pub fn normalize(&self) -> Multiaddr {
+        let mut components = self.iter().collect::<Vec<_>>();
+        // An `/ip6zone` belongs to the `/ip6` following it. Only if there is none, it is
+        // moved in front of the `/ip6` preceding it, unless that one already has a zone.
+        let mut i = 0;
+        while i + 1 < components.len() {
+            if let (Protocol::Ip6(_), Protocol::Ip6zone(_)) = (&components[i], &components[i + 1]) {
+                let zoned = i > 0 && matches!(components[i - 1], Protocol::Ip6zone(_));
+                if !zoned && !matches!(components.get(i + 2), Some(Protocol::Ip6(_))) {
+                    components.swap(i, i + 1);
+                    i += 1;
+                }
+            }
+            i += 1;
+        }
+
+        let mut addr = Multiaddr::with_capacity(self.len());
+        let mut zoned = false;
+        let mut components = components.into_iter().peekable();
+        while let Some(p) = components.next() {
+            match p {
+                Protocol::Https => {
+                    addr.push(Protocol::Tls);
+                    addr.push(Protocol::Http);
+                }
+                Protocol::Ws(path) => push_ws(&mut addr, &path),
+                Protocol::Wss(path) => {
+                    addr.push(Protocol::Tls);
+                    push_ws(&mut addr, &path);
+                }
+                Protocol::Ip6(ip) if !zoned => match (ip.to_ipv4_mapped(), components.peek()) {
+                    (Some(_), Some(Protocol::Ipcidr(prefix))) if *prefix < 96 => {
+                        addr.push(Protocol::Ip6(ip))
+                    }
+                    (Some(ip), Some(Protocol::Ipcidr(prefix))) => {
+                        let prefix = *prefix - 96;
+                        components.next();
+                        addr.push(Protocol::Ip4(ip));
+                        addr.push(Protocol::Ipcidr(prefix));
+                    }
+                    (Some(ip), _) => addr.push(Protocol::Ip4(ip)),
+                    (None, _) => addr.push(Protocol::Ip6(ip)),
+                },
+                Protocol::Dns(name) => addr.push(Protocol::Dns(normalize_name(name))),
+                Protocol::Dns4(name) => addr.push(Protocol::Dns4(normalize_name(name))),
+                Protocol::Dns6(name) => addr.push(Protocol::Dns6(normalize_name(name))),
+                Protocol::Dnsaddr(name) => addr.push(Protocol::Dnsaddr(normalize_name(name))),
+                Protocol::Sni(name) => addr.push(Protocol::Sni(normalize_name(name))),
+                Protocol::Ip6zone(zone) => {
+                    addr.push(Protocol::Ip6zone(zone));
+                    zoned = true;
+                    continue;
+                }
+                p => addr.push(p),
+            }
+            zoned = false;
+        }
+        addr
+    }
+
+    /// Checks whether this multiaddress and `other` have the same
+    /// [canonical form](MultiaddrSlice::normalize).
+    pub fn is_equivalent(&self, other: &MultiaddrSlice) -> bool {
+        self == other || self.normalize() == other.normalize()
+    }
+}
+
+/// Pushes a `/ws` with the given path in the standard form, i.e. followed by an `/http-path`
+/// unless the path is `/`.
+fn push_ws(addr: &mut Multiaddr, path: &str) {
+    ws_path::push_standard(addr, Protocol::Ws, path.strip_prefix('/').unwrap_or(path));
+}
+
+fn normalize_name(name: Cow<'_, str>) -> Cow<'_, str> {
+    let trimmed = name.trim_end_matches('.');
+    if trimmed.len() == name.len() && !trimmed.bytes().any(|b| b.is_ascii_uppercase()) {
+        return name;
+    }
+    Cow::Owned(trimmed.to_ascii_lowercase())
+}
@@ -1,4 +1,6 @@
-use std::{borrow::Cow, fmt};
+use alloc::borrow::Cow;
+use alloc::format;
+use core::fmt;
 
 /// Represents an Onion v3 address
 #[derive(Clone)]
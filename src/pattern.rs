@@ -0,0 +1,427 @@
+use crate::{protocol, registry, MultiaddrSlice, PeerId, Protocol};
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{error, fmt, net::IpAddr, str::FromStr};
+
+/// A pattern describing a family of multiaddresses, e.g. "TCP over IP with an optional peer id".
+///
+/// # Syntax
+///
+/// A pattern is a sequence of `/`-separated elements, each matching one component of a
+/// multiaddress:
+///
+/// - `tcp` matches a `/tcp` component with any value. The name is compared against
+///   [`Protocol::tag`], so `x-parity-ws` must be used for `/ws` with a path.
+/// - `{ip4|ip6|dns*}` matches any of the listed protocols. An alternative ending in `*`
+///   matches all protocols whose name starts with the given prefix.
+/// - `...` matches any sequence of components, including none.
+///
+/// An element may be followed by a quantifier: `?` (zero or one), `*` (zero or more) or
+/// `+` (one or more). Note that a trailing `*` on a bare name is a quantifier, i.e. `dns*`
+/// matches any number of `/dns` components, whereas `{dns*}` matches one of `/dns`, `/dns4`,
+/// `/dns6` or `/dnsaddr`.
+///
+/// The segment following an element may constrain the value of the matched component:
+///
+/// - `*` matches any value.
+/// - `<name>` matches any value and captures the component under `name`.
+/// - Any other segment that is not itself an element is compared against the value's
+///   string representation, e.g. `/ip4/127.0.0.1` or `/tcp/443`.
+///
+/// Omitting the value segment is equivalent to `*`.
+///
+/// Several patterns can be combined with `|` at the top level. The combined pattern matches
+/// if any of them matches.
+///
+/// # Example
+///
+/// ```
+/// use multiaddr::{Multiaddr, Pattern};
+///
+/// let pattern: Pattern = "/{ip4|ip6|dns*}/<host>/tcp/<port>/ws?/p2p?/<peer>".parse().unwrap();
+///
+/// let addr: Multiaddr = "/dns4/example.com/tcp/443/ws".parse().unwrap();
+/// let captures = pattern.captures(&addr).unwrap();
+/// assert_eq!(captures.domain("host"), Some("example.com"));
+/// assert_eq!(captures.port("port"), Some(443));
+/// assert_eq!(captures.peer_id("peer"), None);
+///
+/// assert!(!pattern.matches(&"/ip4/127.0.0.1/udp/443".parse::<Multiaddr>().unwrap()));
+/// ```
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    source: String,
+    alternatives: Vec<Vec<Element>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Element {
+    /// `...`
+    Any,
+    Protocol {
+        names: Vec<Name>,
+        quantifier: Quantifier,
+        value: Value,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Name {
+    Exact(String),
+    Prefix(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Quantifier {
+    One,
+    Optional,
+    ZeroOrMore,
+    OneOrMore,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value {
+    Any,
+    Capture(String),
+    Literal(String),
+}
+
+impl Pattern {
+    /// Parses a pattern, see the type-level documentation for the syntax.
+    pub fn new(pattern: &str) -> Result<Pattern, PatternError> {
+        let alternatives = split_alternatives(pattern)
+            .into_iter()
+            .map(|s| parse_sequence(s.trim()))
+            .collect::<Result<_, _>>()?;
+        Ok(Pattern {
+            source: pattern.to_owned(),
+            alternatives,
+        })
+    }
+
+    /// Checks whether the given multiaddress matches this pattern.
+    pub fn matches(&self, addr: &MultiaddrSlice) -> bool {
+        self.captures(addr).is_some()
+    }
+
+    /// Matches the given multiaddress against this pattern and returns the captured components.
+    pub fn captures<'a>(&self, addr: &'a MultiaddrSlice) -> Option<Captures<'a>> {
+        let components = addr.iter().collect::<Vec<_>>();
+        self.alternatives.iter().find_map(|elements| {
+            let mut captures = Vec::new();
+            let mut failed = Failed::new(elements, &components);
+            if match_sequence(elements, &components, &mut captures, &mut failed) {
+                Some(Captures { values: captures })
+            } else {
+                None
+            }
+        })
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = PatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Pattern::new(s)
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Splits a pattern at the `|` characters that are not enclosed in braces.
+fn split_alternatives(pattern: &str) -> Vec<&str> {
+    let mut alternatives = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in pattern.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            '|' if depth == 0 => {
+                alternatives.push(&pattern[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    alternatives.push(&pattern[start..]);
+    alternatives
+}
+
+fn parse_sequence(pattern: &str) -> Result<Vec<Element>, PatternError> {
+    let mut segments = pattern.split('/').peekable();
+    if segments.next() != Some("") {
+        return Err(PatternError::MissingLeadingSlash(pattern.to_owned()));
+    }
+
+    let mut elements = Vec::new();
+    while let Some(segment) = segments.next() {
+        if segment == "..." {
+            elements.push(Element::Any);
+            continue;
+        }
+        let (names, quantifier) = parse_element(segment)
+            .ok_or_else(|| PatternError::InvalidElement(segment.to_owned()))?;
+        let value = match segments.next_if(|s| parse_element(s).is_none() && *s != "...") {
+            None | Some("*") => Value::Any,
+            Some(s) => match s.strip_prefix('<').and_then(|s| s.strip_suffix('>')) {
+                Some("") => return Err(PatternError::InvalidValue(s.to_owned())),
+                Some(name) => Value::Capture(name.to_owned()),
+                None => Value::Literal(s.to_owned()),
+            },
+        };
+        elements.push(Element::Protocol {
+            names,
+            quantifier,
+            value,
+        });
+    }
+    Ok(elements)
+}
+
+/// Parses a segment as an element, returning `None` if it isn't one.
+fn parse_element(segment: &str) -> Option<(Vec<Name>, Quantifier)> {
+    let (body, quantifier) = match segment.as_bytes().last()? {
+        b'?' => (&segment[..segment.len() - 1], Quantifier::Optional),
+        b'*' => (&segment[..segment.len() - 1], Quantifier::ZeroOrMore),
+        b'+' => (&segment[..segment.len() - 1], Quantifier::OneOrMore),
+        _ => (segment, Quantifier::One),
+    };
+
+    if let Some(inner) = body.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+        let names = inner
+            .split('|')
+            .map(|name| match name.strip_suffix('*') {
+                Some(prefix) if is_name(prefix) => Some(Name::Prefix(prefix.to_owned())),
+                Some(_) => None,
+                None if is_known(name) => Some(Name::Exact(canonical(name).to_owned())),
+                None => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        return Some((names, quantifier));
+    }
+
+    if is_known(body) {
+        return Some((vec![Name::Exact(canonical(body).to_owned())], quantifier));
+    }
+    None
+}
+
+fn is_name(s: &str) -> bool {
+    !s.is_empty()
+        && s.bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+}
+
+fn is_known(name: &str) -> bool {
+    protocol::is_builtin_name(name) || registry::by_name(name).is_some()
+}
+
+/// Maps protocol name aliases onto the name returned by [`Protocol::tag`].
+fn canonical(name: &str) -> &str {
+    match name {
+        "ipfs" => "p2p",
+        name => name,
+    }
+}
+
+/// The `(elements, components)` suffixes that are known not to match, indexed by their lengths.
+///
+/// Whether a suffix of a pattern matches a suffix of an address does not depend on how the
+/// preceding components were matched, so each pair is tried at most once. This keeps the
+/// backtracking polynomial in the lengths of the pattern and the address.
+struct Failed {
+    width: usize,
+    failed: Vec<bool>,
+}
+
+impl Failed {
+    fn new(elements: &[Element], components: &[Protocol<'_>]) -> Self {
+        let width = components.len() + 1;
+        Failed {
+            width,
+            failed: vec![false; (elements.len() + 1) * width],
+        }
+    }
+
+    fn index(&self, elements: &[Element], components: &[Protocol<'_>]) -> usize {
+        elements.len() * self.width + components.len()
+    }
+}
+
+fn match_sequence<'a>(
+    elements: &[Element],
+    components: &[Protocol<'a>],
+    captures: &mut Vec<(String, Protocol<'a>)>,
+    failed: &mut Failed,
+) -> bool {
+    let index = failed.index(elements, components);
+    if failed.failed[index] {
+        return false;
+    }
+    let matched = match_element(elements, components, captures, failed);
+    if !matched {
+        failed.failed[index] = true;
+    }
+    matched
+}
+
+fn match_element<'a>(
+    elements: &[Element],
+    components: &[Protocol<'a>],
+    captures: &mut Vec<(String, Protocol<'a>)>,
+    failed: &mut Failed,
+) -> bool {
+    let (element, rest) = match elements.split_first() {
+        Some(split) => split,
+        None => return components.is_empty(),
+    };
+
+    let (names, quantifier, value) = match element {
+        Element::Any => {
+            return (0..=components.len())
+                .any(|n| match_sequence(rest, &components[n..], captures, failed))
+        }
+        Element::Protocol {
+            names,
+            quantifier,
+            value,
+        } => (names, *quantifier, value),
+    };
+
+    let (min, max) = match quantifier {
+        Quantifier::One => (1, 1),
+        Quantifier::Optional => (0, 1),
+        Quantifier::ZeroOrMore => (0, usize::MAX),
+        Quantifier::OneOrMore => (1, usize::MAX),
+    };
+
+    let matching = components
+        .iter()
+        .take(max)
+        .take_while(|p| matches_component(names, value, p))
+        .count();
+
+    for n in (min..=matching).rev() {
+        let len = captures.len();
+        if let Value::Capture(name) = value {
+            captures.extend(components[..n].iter().map(|p| (name.clone(), p.clone())));
+        }
+        if match_sequence(rest, &components[n..], captures, failed) {
+            return true;
+        }
+        captures.truncate(len);
+    }
+    false
+}
+
+fn matches_component(names: &[Name], value: &Value, p: &Protocol<'_>) -> bool {
+    let tag = p.tag();
+    let name_matches = names.iter().any(|name| match name {
+        Name::Exact(name) => name == tag,
+        Name::Prefix(prefix) => tag.starts_with(prefix.as_str()),
+    });
+    if !name_matches {
+        return false;
+    }
+    match value {
+        Value::Any | Value::Capture(_) => true,
+        Value::Literal(literal) => {
+            let s = p.to_string();
+            s.get(tag.len() + 2..).unwrap_or_default() == literal
+        }
+    }
+}
+
+/// Components captured by [`Pattern::captures`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Captures<'a> {
+    values: Vec<(String, Protocol<'a>)>,
+}
+
+impl<'a> Captures<'a> {
+    /// Returns the first component captured under `name`.
+    pub fn get(&self, name: &str) -> Option<&Protocol<'a>> {
+        self.values.iter().find(|(n, _)| n == name).map(|(_, p)| p)
+    }
+
+    /// Returns all components captured under `name`, e.g. for a repeated `certhash*/<hash>`.
+    pub fn get_all<'s>(&'s self, name: &'s str) -> impl Iterator<Item = &'s Protocol<'a>> + 's {
+        self.values
+            .iter()
+            .filter(move |(n, _)| n == name)
+            .map(|(_, p)| p)
+    }
+
+    /// Returns the IP address captured under `name`, if it is an `/ip4` or `/ip6` component.
+    pub fn ip(&self, name: &str) -> Option<IpAddr> {
+        match self.get(name)? {
+            Protocol::Ip4(ip) => Some(IpAddr::V4(*ip)),
+            Protocol::Ip6(ip) => Some(IpAddr::V6(*ip)),
+            _ => None,
+        }
+    }
+
+    /// Returns the domain name captured under `name`, if it is one of the `/dns` components.
+    pub fn domain(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            Protocol::Dns(s) | Protocol::Dns4(s) | Protocol::Dns6(s) | Protocol::Dnsaddr(s) => {
+                Some(s)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the port captured under `name`, if it is a `/tcp`, `/udp`, `/sctp` or `/dccp`
+    /// component.
+    pub fn port(&self, name: &str) -> Option<u16> {
+        match self.get(name)? {
+            Protocol::Tcp(port)
+            | Protocol::Udp(port)
+            | Protocol::Sctp(port)
+            | Protocol::Dccp(port) => Some(*port),
+            _ => None,
+        }
+    }
+
+    /// Returns the peer id captured under `name`, if it is a `/p2p` component.
+    pub fn peer_id(&self, name: &str) -> Option<PeerId> {
+        match self.get(name)? {
+            Protocol::P2p(peer) => Some(*peer),
+            _ => None,
+        }
+    }
+}
+
+/// Error while parsing a [`Pattern`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternError {
+    /// The pattern, or one of its `|`-separated alternatives, does not start with `/`.
+    MissingLeadingSlash(String),
+    /// The segment is neither a known protocol, an alternation, nor `...`.
+    InvalidElement(String),
+    /// The value segment is malformed, e.g. an empty capture `<>`.
+    InvalidValue(String),
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternError::MissingLeadingSlash(s) => write!(f, "pattern `{s}` must start with `/`"),
+            PatternError::InvalidElement(s) => write!(f, "invalid pattern element `{s}`"),
+            PatternError::InvalidValue(s) => write!(f, "invalid pattern value `{s}`"),
+        }
+    }
+}
+
+impl error::Error for PatternError {}
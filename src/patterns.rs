@@ -0,0 +1,69 @@
+//! Predefined [`Pattern`]s for the transports modelled by [`Protocol`](crate::Protocol).
+//!
+//! Each pattern captures the host under `host`, the port under `port` and the trailing peer
+//! id, if any, under `peer`. Patterns of relayed addresses capture the relay's peer id under
+//! `relay`, and patterns with certificate hashes capture them under `certhash`.
+//!
+//! # Example
+//!
+//! ```
+//! use multiaddr::{patterns, Multiaddr};
+//!
+//! let addr: Multiaddr = "/ip4/1.2.3.4/udp/443/quic-v1".parse().unwrap();
+//! assert!(patterns::quic_v1().matches(&addr));
+//! assert!(!patterns::tcp().matches(&addr));
+//! ```
+
+use crate::Pattern;
+use std::sync::OnceLock;
+
+macro_rules! patterns {
+    ($($(#[$meta:meta])* $name:ident => $pattern:expr;)+) => {
+        $(
+            $(#[$meta])*
+            pub fn $name() -> &'static Pattern {
+                static PATTERN: OnceLock<Pattern> = OnceLock::new();
+                PATTERN.get_or_init(|| Pattern::new($pattern).expect("predefined patterns are valid"))
+            }
+        )+
+    };
+}
+
+macro_rules! host {
+    () => {
+        "/{ip4|ip6|dns|dns4|dns6}/<host>"
+    };
+}
+
+patterns! {
+    /// `/ip4`, `/ip6` or `/dns*` followed by `/tcp`.
+    tcp => concat!(host!(), "/tcp/<port>/p2p?/<peer>");
+    /// `/ip4`, `/ip6` or `/dns*` followed by `/udp`.
+    udp => concat!(host!(), "/udp/<port>/p2p?/<peer>");
+    /// Draft-29 QUIC, i.e. `/udp/<port>/quic`.
+    quic => concat!(host!(), "/udp/<port>/quic/p2p?/<peer>");
+    /// QUIC version 1, i.e. `/udp/<port>/quic-v1`.
+    quic_v1 => concat!(host!(), "/udp/<port>/quic-v1/p2p?/<peer>");
+    /// Plain WebSocket over TCP, with or without a path.
+    websocket => concat!(host!(), "/tcp/<port>/{ws|x-parity-ws}/http-path?/p2p?/<peer>");
+    /// Secure WebSocket over TCP, either as `/tls/ws` (optionally with `/sni`) or as the
+    /// deprecated `/wss`.
+    websocket_secure => concat!(
+        host!(), "/tcp/<port>/tls/sni?/{ws|x-parity-ws}/http-path?/p2p?/<peer> | ",
+        host!(), "/tcp/<port>/{wss|x-parity-wss}/http-path?/p2p?/<peer>"
+    );
+    /// WebTransport over QUIC version 1, with any number of certificate hashes.
+    webtransport => concat!(
+        host!(), "/udp/<port>/quic-v1/webtransport/certhash*/<certhash>/p2p?/<peer>"
+    );
+    /// Browser-to-browser WebRTC, reached through a circuit relay.
+    webrtc => "/.../p2p/<relay>/p2p-circuit/webrtc/p2p?/<peer>";
+    /// WebRTC direct over UDP, with any number of certificate hashes.
+    webrtc_direct => concat!(
+        host!(), "/udp/<port>/webrtc-direct/certhash*/<certhash>/p2p?/<peer>"
+    );
+    /// An address reached through a circuit relay.
+    circuit => "/.../p2p/<relay>/p2p-circuit/p2p?/<peer>";
+    /// A Tor onion service, version 2 or 3. The onion address is captured under `host`.
+    onion => "/{onion|onion3}/<host>/p2p?/<peer>";
+}
@@ -0,0 +1,162 @@
+use alloc::{string::String, vec::Vec};
+use core::{convert::TryFrom, error, fmt, str::FromStr};
+
+/// Peer ids are at most 64 bytes long, like in `libp2p-identity`.
+type Multihash = multihash::Multihash<64>;
+
+/// Public keys of up to this many bytes are inlined into their peer id.
+const MAX_INLINE_KEY_LENGTH: usize = 42;
+
+const MULTIHASH_IDENTITY_CODE: u64 = 0;
+const MULTIHASH_SHA256_CODE: u64 = 0x12;
+
+/// The identifier of a peer, as carried by [`Protocol::P2p`](crate::Protocol::P2p).
+///
+/// A peer id is a multihash of the public key of the peer, see the
+/// [peer id spec](https://github.com/libp2p/specs/blob/master/peer-ids/peer-ids.md). This
+/// type only validates the multihash, like `libp2p_identity::PeerId`, without depending on
+/// any cryptography. With the `libp2p-identity` feature, it converts from and into
+/// `libp2p_identity::PeerId`.
+///
+/// # Example
+///
+/// ```
+/// use multiaddr::{Multiaddr, PeerId};
+///
+/// let peer: PeerId = "QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC".parse().unwrap();
+/// let addr: Multiaddr = "/ip4/1.2.3.4/tcp/80".parse().unwrap();
+/// assert_eq!(
+///     addr.with_p2p(peer).unwrap().to_string(),
+///     "/ip4/1.2.3.4/tcp/80/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC"
+/// );
+/// ```
+///
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PeerId {
+    multihash: Multihash,
+}
+
+impl PeerId {
+    /// Parses a peer id from the bytes of its multihash.
+    pub fn from_bytes(data: &[u8]) -> Result<PeerId, ParsePeerIdError> {
+        let multihash = Multihash::from_bytes(data).map_err(ParsePeerIdError::InvalidMultihash)?;
+        PeerId::from_multihash(multihash)
+            .map_err(|multihash| ParsePeerIdError::UnsupportedCode(multihash.code()))
+    }
+
+    /// Turns a multihash into a peer id.
+    ///
+    /// Only sha2-256 multihashes and identity multihashes of up to 42 bytes are peer ids,
+    /// other multihashes are returned unchanged.
+    pub fn from_multihash(multihash: Multihash) -> Result<PeerId, Multihash> {
+        match multihash.code() {
+            MULTIHASH_SHA256_CODE => Ok(PeerId { multihash }),
+            MULTIHASH_IDENTITY_CODE if multihash.digest().len() <= MAX_INLINE_KEY_LENGTH => {
+                Ok(PeerId { multihash })
+            }
+            _ => Err(multihash),
+        }
+    }
+
+    /// Returns the multihash of this peer id.
+    pub fn as_multihash(&self) -> &Multihash {
+        &self.multihash
+    }
+
+    /// Returns the bytes of the multihash of this peer id.
+    pub fn to_bytes(self) -> Vec<u8> {
+        self.multihash.to_bytes()
+    }
+
+    /// Returns the base58btc encoding of this peer id, its string representation.
+    pub fn to_base58(self) -> String {
+        multibase::Base::Base58Btc.encode(self.to_bytes())
+    }
+}
+
+impl fmt::Debug for PeerId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PeerId").field(&self.to_base58()).finish()
+    }
+}
+
+impl fmt::Display for PeerId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_base58().fmt(f)
+    }
+}
+
+impl FromStr for PeerId {
+    type Err = ParsePeerIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = multibase::Base::Base58Btc
+            .decode(s)
+            .map_err(|_| ParsePeerIdError::InvalidBase58)?;
+        PeerId::from_bytes(&bytes)
+    }
+}
+
+impl TryFrom<Multihash> for PeerId {
+    type Error = Multihash;
+
+    fn try_from(multihash: Multihash) -> Result<Self, Self::Error> {
+        PeerId::from_multihash(multihash)
+    }
+}
+
+impl AsRef<Multihash> for PeerId {
+    fn as_ref(&self) -> &Multihash {
+        &self.multihash
+    }
+}
+
+#[cfg(feature = "libp2p-identity")]
+impl From<libp2p_identity::PeerId> for PeerId {
+    fn from(peer: libp2p_identity::PeerId) -> Self {
+        PeerId {
+            multihash: *peer.as_ref(),
+        }
+    }
+}
+
+#[cfg(feature = "libp2p-identity")]
+impl From<PeerId> for libp2p_identity::PeerId {
+    fn from(peer: PeerId) -> Self {
+        libp2p_identity::PeerId::from_multihash(peer.multihash)
+            .expect("both types accept the same multihashes")
+    }
+}
+
+/// Error while parsing a [`PeerId`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ParsePeerIdError {
+    /// The string is not base58btc encoded.
+    InvalidBase58,
+    /// The bytes are not a multihash.
+    InvalidMultihash(multihash::Error),
+    /// The multihash is neither a sha2-256 nor a short enough identity multihash.
+    UnsupportedCode(u64),
+}
+
+impl fmt::Display for ParsePeerIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParsePeerIdError::InvalidBase58 => f.write_str("invalid base58btc encoding"),
+            ParsePeerIdError::InvalidMultihash(e) => write!(f, "invalid multihash: {e}"),
+            ParsePeerIdError::UnsupportedCode(code) => {
+                write!(f, "unsupported multihash code {code}")
+            }
+        }
+    }
+}
+
+impl error::Error for ParsePeerIdError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ParsePeerIdError::InvalidMultihash(e) => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -1,64 +1,241 @@
 use crate::onion_addr::Onion3Addr;
-use crate::{Error, PeerId, Result};
-use arrayref::array_ref;
-use byteorder::{BigEndian, ByteOrder, ReadBytesExt, WriteBytesExt};
-use data_encoding::BASE32;
-use std::{
+use crate::registry::{self, ValueCodec};
+use crate::{errors, Error, PeerId, Result};
+use alloc::{
     borrow::Cow,
+    boxed::Box,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use arrayref::array_ref;
+use byteorder::{BigEndian, ByteOrder};
+use core::{
     convert::From,
     fmt,
-    io::{Cursor, Write},
     net::{IpAddr, Ipv4Addr, Ipv6Addr},
     str::{self, FromStr},
 };
+use data_encoding::{BASE32, HEXLOWER, HEXLOWER_PERMISSIVE};
 use unsigned_varint::{decode, encode};
 
+/// Defines the multicodec constants, [`BUILTIN`] and [`ProtocolKind`] with its conversions
+/// from a single table of the built-in protocols.
+///
+/// Each row is `Kind(CONST = code, "name" | "alias"..., size[, deprecated])`, where `size` is
+/// a [`ValueSize`].
+macro_rules! protocols {
+    (@deprecated) => {
+        false
+    };
+    (@deprecated deprecated) => {
+        true
+    };
+    ($(
+        $(#[doc = $doc:literal])*
+        $kind:ident($code:ident = $value:literal, $name:literal $(| $alias:literal)*,
+            $size:ident $(($bits:literal))? $(, $deprecated:ident)?),
+    )*) => {
+        $(const $code: u32 = $value;)*
+
+        /// Codes and names of all protocols known to this crate.
+        const BUILTIN: &[(u32, &str)] = &[$(($code, $name), $(($code, $alias),)*)*];
+
+        /// The kind of a built-in [`Protocol`], without its value.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use multiaddr::{Protocol, ProtocolKind, ValueSize};
+        ///
+        /// assert_eq!(Protocol::Tcp(80).kind(), Some(ProtocolKind::Tcp));
+        /// assert_eq!(ProtocolKind::Tcp.code(), 6);
+        /// assert_eq!(ProtocolKind::Tcp.value_size(), ValueSize::Fixed(16));
+        /// assert_eq!("ipfs".parse::<ProtocolKind>().unwrap(), ProtocolKind::P2p);
+        /// assert_eq!(ProtocolKind::try_from(421).unwrap().to_string(), "p2p");
+        /// ```
+        ///
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[non_exhaustive]
+        pub enum ProtocolKind {
+            $($(#[doc = $doc])* $kind,)*
+        }
+
+        impl ProtocolKind {
+            /// All protocol kinds, in no particular order.
+            pub const ALL: &'static [ProtocolKind] = &[$(ProtocolKind::$kind,)*];
+
+            /// Returns the multicodec of this protocol.
+            pub fn code(self) -> u32 {
+                match self {
+                    $(ProtocolKind::$kind => $code,)*
+                }
+            }
+
+            /// Returns the name of this protocol in the string representation.
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(ProtocolKind::$kind => $name,)*
+                }
+            }
+
+            /// Returns the size of the value of this protocol.
+            pub fn value_size(self) -> ValueSize {
+                match self {
+                    $(ProtocolKind::$kind => ValueSize::$size $(($bits))?,)*
+                }
+            }
+
+            /// Checks whether this protocol is deprecated in favour of another one.
+            pub fn is_deprecated(self) -> bool {
+                match self {
+                    $(ProtocolKind::$kind => protocols!(@deprecated $($deprecated)?),)*
+                }
+            }
+        }
+
+        impl FromStr for ProtocolKind {
+            type Err = Error;
+
+            fn from_str(s: &str) -> Result<Self> {
+                match s {
+                    $($name $(| $alias)* => Ok(ProtocolKind::$kind),)*
+                    _ => Err(Error::UnknownProtocolString(s.to_string())),
+                }
+            }
+        }
+
+        impl TryFrom<u32> for ProtocolKind {
+            type Error = Error;
+
+            fn try_from(code: u32) -> Result<Self> {
+                match code {
+                    $($code => Ok(ProtocolKind::$kind),)*
+                    _ => Err(Error::UnknownProtocolId(code)),
+                }
+            }
+        }
+    };
+}
+
 // All the values are obtained by converting hexadecimal protocol codes to u32.
 // Protocols as well as their corresponding codes are defined in
 // https://github.com/multiformats/multiaddr/blob/master/protocols.csv .
-const DCCP: u32 = 33;
-const DNS: u32 = 53;
-const DNS4: u32 = 54;
-const DNS6: u32 = 55;
-const DNSADDR: u32 = 56;
-const HTTP: u32 = 480;
-const HTTPS: u32 = 443; // Deprecated - alias for /tls/http
-const IP4: u32 = 4;
-const IP6: u32 = 41;
-const P2P_WEBRTC_DIRECT: u32 = 276; // Deprecated
-const P2P_WEBRTC_STAR: u32 = 275; // Deprecated
-const WEBRTC_DIRECT: u32 = 280;
-const CERTHASH: u32 = 466;
-const P2P_WEBSOCKET_STAR: u32 = 479; // Deprecated
-const MEMORY: u32 = 777;
-const ONION: u32 = 444;
-const ONION3: u32 = 445;
-const P2P: u32 = 421;
-const P2P_CIRCUIT: u32 = 290;
-const QUIC: u32 = 460;
-const QUIC_V1: u32 = 461;
-const SCTP: u32 = 132;
-const TCP: u32 = 6;
-const TLS: u32 = 448;
-const NOISE: u32 = 454;
-const UDP: u32 = 273;
-const UDT: u32 = 301;
-const UNIX: u32 = 400;
-const UTP: u32 = 302;
-const WEBTRANSPORT: u32 = 465;
-const WS: u32 = 477;
-const WS_WITH_PATH: u32 = 4770; // Note: not standard
-const WSS: u32 = 478; // Deprecated - alias for /tls/ws
-const WSS_WITH_PATH: u32 = 4780; // Note: not standard
-const IP6ZONE: u32 = 42;
-const IPCIDR: u32 = 43;
-// const IPFS: u32 = 421; // Deprecated
-const GARLIC64: u32 = 446;
-const GARLIC32: u32 = 447;
-const SNI: u32 = 449;
-const P2P_STARDUST: u32 = 277; // Deprecated
-const WEBRTC: u32 = 281;
-const HTTP_PATH: u32 = 481;
+protocols! {
+    Dccp(DCCP = 33, "dccp", Fixed(16)),
+    Dns(DNS = 53, "dns", Variable),
+    Dns4(DNS4 = 54, "dns4", Variable),
+    Dns6(DNS6 = 55, "dns6", Variable),
+    Dnsaddr(DNSADDR = 56, "dnsaddr", Variable),
+    Http(HTTP = 480, "http", None),
+    /// Deprecated alias for `/tls/http`.
+    Https(HTTPS = 443, "https", None, deprecated),
+    Ip4(IP4 = 4, "ip4", Fixed(32)),
+    Ip6(IP6 = 41, "ip6", Fixed(128)),
+    P2pWebRtcDirect(P2P_WEBRTC_DIRECT = 276, "p2p-webrtc-direct", None, deprecated),
+    P2pWebRtcStar(P2P_WEBRTC_STAR = 275, "p2p-webrtc-star", None, deprecated),
+    WebRTCDirect(WEBRTC_DIRECT = 280, "webrtc-direct", None),
+    Certhash(CERTHASH = 466, "certhash", Variable),
+    P2pWebSocketStar(P2P_WEBSOCKET_STAR = 479, "p2p-websocket-star", None, deprecated),
+    Memory(MEMORY = 777, "memory", Fixed(64)),
+    Onion(ONION = 444, "onion", Fixed(96)),
+    Onion3(ONION3 = 445, "onion3", Fixed(296)),
+    /// `/p2p`, also written as the deprecated `/ipfs`.
+    P2p(P2P = 421, "p2p" | "ipfs", Variable),
+    P2pCircuit(P2P_CIRCUIT = 290, "p2p-circuit", None),
+    Quic(QUIC = 460, "quic", None),
+    QuicV1(QUIC_V1 = 461, "quic-v1", None),
+    Sctp(SCTP = 132, "sctp", Fixed(16)),
+    Tcp(TCP = 6, "tcp", Fixed(16)),
+    Tls(TLS = 448, "tls", None),
+    Noise(NOISE = 454, "noise", None),
+    Udp(UDP = 273, "udp", Fixed(16)),
+    Udt(UDT = 301, "udt", None),
+    Unix(UNIX = 400, "unix", Path),
+    Utp(UTP = 302, "utp", None),
+    WebTransport(WEBTRANSPORT = 465, "webtransport", None),
+    Ws(WS = 477, "ws", None),
+    /// `/ws` with a path, not standardized.
+    XParityWs(WS_WITH_PATH = 4770, "x-parity-ws", Variable),
+    /// Deprecated alias for `/tls/ws`.
+    Wss(WSS = 478, "wss", None, deprecated),
+    /// `/wss` with a path, not standardized.
+    XParityWss(WSS_WITH_PATH = 4780, "x-parity-wss", Variable),
+    Ip6zone(IP6ZONE = 42, "ip6zone", Variable),
+    Ipcidr(IPCIDR = 43, "ipcidr", Fixed(8)),
+    Garlic64(GARLIC64 = 446, "garlic64", Variable),
+    Garlic32(GARLIC32 = 447, "garlic32", Variable),
+    Sni(SNI = 449, "sni", Variable),
+    P2pStardust(P2P_STARDUST = 277, "p2p-stardust", None, deprecated),
+    WebRTC(WEBRTC = 281, "webrtc", None),
+    HttpPath(HTTP_PATH = 481, "http-path", Variable),
+    Thread(THREAD = 406, "thread", Variable),
+    Shs(SHS = 456, "shs", Variable),
+    PlaintextV2(PLAINTEXTV2 = 7367777, "plaintextv2", None),
+    Scion(SCION = 13639680, "scion", Variable),
+    Silverpine(SILVERPINE = 16194, "silverpine", None),
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn is_builtin_code(code: u32) -> bool {
+    BUILTIN.iter().any(|(c, _)| *c == code)
+}
+
+pub(crate) fn is_builtin_name(name: &str) -> bool {
+    BUILTIN.iter().any(|(_, n)| *n == name)
+}
+
+/// Returns the name of a built-in or registered protocol with the given name.
+pub(crate) fn known_name(name: &str) -> Option<&'static str> {
+    BUILTIN
+        .iter()
+        .find(|(_, n)| *n == name)
+        .map(|(_, n)| *n)
+        .or_else(|| registry::by_name(name).map(|p| p.name))
+}
+
+/// Returns the name of the built-in or registered protocol with the given code.
+pub(crate) fn name_of(code: u32) -> Option<&'static str> {
+    BUILTIN
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, n)| *n)
+        .or_else(|| registry::by_code(code).map(|p| p.name))
+}
+
+/// Returns the built-in protocol name closest to `name`, if it is within an edit distance
+/// of a third of the length of `name`, but at least one.
+pub(crate) fn closest_name(name: &str) -> Option<&'static str> {
+    let name = name.to_ascii_lowercase();
+    let max = (name.len() / 3).max(1);
+    BUILTIN
+        .iter()
+        .map(|(_, n)| (edit_distance(&name, n), *n))
+        .filter(|(distance, _)| *distance <= max)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, n)| n)
+}
+
+/// The number of insertions, deletions, substitutions and transpositions of adjacent
+/// characters needed to turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    d[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        d[i][0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
 
 /// Type-alias for how multi-addresses use `Multihash`.
 ///
@@ -117,10 +294,24 @@ pub enum Protocol<'a> {
     Noise,
     Udp(u16),
     Udt,
+    /// The path of a Unix domain socket.
+    ///
+    /// A path starting with a NUL byte is the name of a socket in the Linux abstract
+    /// namespace. The string representation is a single segment in which `/` and `%` are
+    /// percent-encoded, e.g. `/unix/%2Ftmp%2Fnode.sock` or `/unix/node.sock`, so that other
+    /// components may follow it.
     Unix(Cow<'a, str>),
     Utp,
     WebTransport,
+    /// A WebSocket with the given path, `/` by default.
+    ///
+    /// A different path is encoded as the non-standard `/x-parity-ws/<path>`. The standard
+    /// `/ws/http-path/<path>` is decoded as a `Ws("/")` followed by a [`Protocol::HttpPath`].
+    /// Use [`Multiaddr::with_ws_path`](crate::Multiaddr::with_ws_path) or
+    /// [`MultiaddrSlice::to_ws_path_encoding`](crate::MultiaddrSlice::to_ws_path_encoding) to
+    /// choose the form explicitly.
     Ws(Cow<'a, str>),
+    /// Like [`Protocol::Ws`], but deprecated in favour of `/tls/ws`.
     Wss(Cow<'a, str>),
     Ip6zone(Cow<'a, str>),
     Ipcidr(u8),
@@ -130,6 +321,97 @@ pub enum Protocol<'a> {
     P2pStardust,
     WebRTC,
     HttpPath(Cow<'a, str>),
+    /// The id of a Textile thread.
+    Thread(Cow<'a, [u8]>),
+    /// The public key of a Secure Scuttlebutt peer, for the secret handshake.
+    Shs(Cow<'a, [u8]>),
+    PlaintextV2,
+    /// The ISD-AS number of a SCION autonomous system, e.g. `1-ff00:0:110`.
+    Scion(Cow<'a, str>),
+    Silverpine,
+    /// A protocol registered at runtime with [`register_protocol`](crate::register_protocol).
+    ///
+    /// Writing a `Custom` protocol whose `code` is not registered, or whose `value` does not
+    /// match the registered [`ValueCodec`], fails. [`Multiaddr::push`](crate::Multiaddr::push)
+    /// and the other infallible ways of building a multiaddr panic on such a protocol, use
+    /// [`Multiaddr::try_push`](crate::Multiaddr::try_push) to handle the error.
+    Custom {
+        code: u32,
+        name: &'static str,
+        value: Cow<'a, [u8]>,
+    },
+}
+
+/// The layer of the network stack a [`Protocol`] belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Layer {
+    /// Addresses a host, e.g. `/ip4`, `/dns` or `/onion3`.
+    Network,
+    /// Carries a connection, e.g. `/tcp`, `/quic-v1` or `/ws`.
+    Transport,
+    /// Secures a connection, e.g. `/tls`, `/noise` or `/certhash`.
+    Security,
+    /// Runs on top of a connection, e.g. `/http`.
+    Application,
+    /// Relays a connection through another peer, i.e. `/p2p-circuit`.
+    Relay,
+    /// Identifies a peer, i.e. `/p2p`.
+    PeerIdentity,
+    /// Addresses a location by path, e.g. `/unix` or `/http-path`.
+    Path,
+    /// A [`Protocol::Custom`] whose layer is unknown.
+    Unknown,
+}
+
+/// The size of the value of a [`ProtocolKind`], as listed in the `size` column of the
+/// protocol table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueSize {
+    /// The protocol carries no value.
+    None,
+    /// The value is exactly this many bits long.
+    Fixed(u32),
+    /// The value is prefixed by its varint-encoded length.
+    Variable,
+    /// Like [`ValueSize::Variable`], but the value is a path.
+    Path,
+}
+
+impl fmt::Display for ProtocolKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl From<ProtocolKind> for u32 {
+    fn from(kind: ProtocolKind) -> u32 {
+        kind.code()
+    }
+}
+
+/// A sink for the binary representation of a [`Protocol`], see [`Protocol::write_bytes`].
+///
+/// With the `std` feature, this is implemented for every [`std::io::Write`]. Without it, it
+/// is implemented for `Vec<u8>`.
+pub trait Writer {
+    /// Writes all of `buf`.
+    fn write_all(&mut self, buf: &[u8]) -> Result<()>;
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write + ?Sized> Writer for W {
+    fn write_all(&mut self, buf: &[u8]) -> Result<()> {
+        std::io::Write::write_all(self, buf).map_err(Error::from)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl Writer for Vec<u8> {
+    fn write_all(&mut self, buf: &[u8]) -> Result<()> {
+        self.extend_from_slice(buf);
+        Ok(())
+    }
 }
 
 impl<'a> Protocol<'a> {
@@ -143,72 +425,74 @@ impl<'a> Protocol<'a> {
     where
         I: Iterator<Item = &'a str>,
     {
-        match iter.next().ok_or(Error::InvalidProtocolString)? {
+        match iter.next().ok_or(Error::MissingValue)? {
             "ip4" => {
-                let s = iter.next().ok_or(Error::InvalidProtocolString)?;
-                Ok(Protocol::Ip4(Ipv4Addr::from_str(s)?))
+                let s = iter.next().ok_or(Error::MissingValue)?;
+                Ok(Protocol::Ip4(
+                    Ipv4Addr::from_str(s).map_err(Error::InvalidIpAddr)?,
+                ))
             }
             "tcp" => {
-                let s = iter.next().ok_or(Error::InvalidProtocolString)?;
-                Ok(Protocol::Tcp(s.parse()?))
+                let s = iter.next().ok_or(Error::MissingValue)?;
+                Ok(Protocol::Tcp(s.parse().map_err(Error::InvalidPort)?))
             }
             "tls" => Ok(Protocol::Tls),
             "noise" => Ok(Protocol::Noise),
             "udp" => {
-                let s = iter.next().ok_or(Error::InvalidProtocolString)?;
-                Ok(Protocol::Udp(s.parse()?))
+                let s = iter.next().ok_or(Error::MissingValue)?;
+                Ok(Protocol::Udp(s.parse().map_err(Error::InvalidPort)?))
             }
             "dccp" => {
-                let s = iter.next().ok_or(Error::InvalidProtocolString)?;
-                Ok(Protocol::Dccp(s.parse()?))
+                let s = iter.next().ok_or(Error::MissingValue)?;
+                Ok(Protocol::Dccp(s.parse().map_err(Error::InvalidPort)?))
             }
             "ip6" => {
-                let s = iter.next().ok_or(Error::InvalidProtocolString)?;
-                Ok(Protocol::Ip6(Ipv6Addr::from_str(s)?))
+                let s = iter.next().ok_or(Error::MissingValue)?;
+                Ok(Protocol::Ip6(
+                    Ipv6Addr::from_str(s).map_err(Error::InvalidIpAddr)?,
+                ))
             }
             "dns" => {
-                let s = iter.next().ok_or(Error::InvalidProtocolString)?;
+                let s = iter.next().ok_or(Error::MissingValue)?;
                 Ok(Protocol::Dns(Cow::Borrowed(s)))
             }
             "dns4" => {
-                let s = iter.next().ok_or(Error::InvalidProtocolString)?;
+                let s = iter.next().ok_or(Error::MissingValue)?;
                 Ok(Protocol::Dns4(Cow::Borrowed(s)))
             }
             "dns6" => {
-                let s = iter.next().ok_or(Error::InvalidProtocolString)?;
+                let s = iter.next().ok_or(Error::MissingValue)?;
                 Ok(Protocol::Dns6(Cow::Borrowed(s)))
             }
             "dnsaddr" => {
-                let s = iter.next().ok_or(Error::InvalidProtocolString)?;
+                let s = iter.next().ok_or(Error::MissingValue)?;
                 Ok(Protocol::Dnsaddr(Cow::Borrowed(s)))
             }
             "sctp" => {
-                let s = iter.next().ok_or(Error::InvalidProtocolString)?;
-                Ok(Protocol::Sctp(s.parse()?))
+                let s = iter.next().ok_or(Error::MissingValue)?;
+                Ok(Protocol::Sctp(s.parse().map_err(Error::InvalidPort)?))
             }
             "udt" => Ok(Protocol::Udt),
             "utp" => Ok(Protocol::Utp),
             "unix" => {
-                let s = iter.next().ok_or(Error::InvalidProtocolString)?;
-                Ok(Protocol::Unix(Cow::Borrowed(s)))
+                let path = String::from_utf8(read_path(iter)?).map_err(|e| e.utf8_error())?;
+                Ok(Protocol::Unix(Cow::Owned(path)))
             }
             "p2p" | "ipfs" => {
-                let s = iter.next().ok_or(Error::InvalidProtocolString)?;
-                let decoded = multibase::Base::Base58Btc.decode(s)?;
-                let peer_id =
-                    PeerId::from_bytes(&decoded).map_err(|e| Error::ParsingError(Box::new(e)))?;
+                let s = iter.next().ok_or(Error::MissingValue)?;
+                let peer_id = s.parse().map_err(|e| Error::InvalidPeerId(Box::new(e)))?;
                 Ok(Protocol::P2p(peer_id))
             }
             "http" => Ok(Protocol::Http),
             "https" => Ok(Protocol::Https),
             "onion" => iter
                 .next()
-                .ok_or(Error::InvalidProtocolString)
+                .ok_or(Error::MissingValue)
                 .and_then(|s| read_onion(&s.to_uppercase()))
                 .map(|(a, p)| Protocol::Onion(Cow::Owned(a), p)),
             "onion3" => iter
                 .next()
-                .ok_or(Error::InvalidProtocolString)
+                .ok_or(Error::MissingValue)
                 .and_then(|s| read_onion3(&s.to_uppercase()))
                 .map(|(a, p)| Protocol::Onion3((a, p).into())),
             "quic" => Ok(Protocol::Quic),
@@ -217,12 +501,12 @@ impl<'a> Protocol<'a> {
             "ws" => Ok(Protocol::Ws(Cow::Borrowed("/"))),
             "wss" => Ok(Protocol::Wss(Cow::Borrowed("/"))),
             "x-parity-ws" => {
-                let s = iter.next().ok_or(Error::InvalidProtocolString)?;
+                let s = iter.next().ok_or(Error::MissingValue)?;
                 let decoded = percent_encoding::percent_decode(s.as_bytes()).decode_utf8()?;
                 Ok(Protocol::Ws(decoded))
             }
             "x-parity-wss" => {
-                let s = iter.next().ok_or(Error::InvalidProtocolString)?;
+                let s = iter.next().ok_or(Error::MissingValue)?;
                 let decoded = percent_encoding::percent_decode(s.as_bytes()).decode_utf8()?;
                 Ok(Protocol::Wss(decoded))
             }
@@ -230,28 +514,31 @@ impl<'a> Protocol<'a> {
             "p2p-webrtc-star" => Ok(Protocol::P2pWebRtcStar),
             "webrtc-direct" => Ok(Protocol::WebRTCDirect),
             "certhash" => {
-                let s = iter.next().ok_or(Error::InvalidProtocolString)?;
-                let (_base, decoded) = multibase::decode(s)?;
-                Ok(Protocol::Certhash(Multihash::from_bytes(&decoded)?))
+                let s = iter.next().ok_or(Error::MissingValue)?;
+                let (_base, decoded) =
+                    multibase::decode(s).map_err(|e| Error::InvalidMultihash(errors::boxed(e)))?;
+                let hash = Multihash::from_bytes(&decoded)
+                    .map_err(|e| Error::InvalidMultihash(e.into()))?;
+                Ok(Protocol::Certhash(hash))
             }
             "p2p-webrtc-direct" => Ok(Protocol::P2pWebRtcDirect),
             "p2p-circuit" => Ok(Protocol::P2pCircuit),
             "memory" => {
-                let s = iter.next().ok_or(Error::InvalidProtocolString)?;
+                let s = iter.next().ok_or(Error::MissingValue)?;
                 Ok(Protocol::Memory(s.parse()?))
             }
             "ip6zone" => {
-                let s = iter.next().ok_or(Error::InvalidProtocolString)?;
+                let s = iter.next().ok_or(Error::MissingValue)?;
                 Ok(Protocol::Ip6zone(Cow::Borrowed(s)))
             }
             "ipcidr" => {
-                let s = iter.next().ok_or(Error::InvalidProtocolString)?;
+                let s = iter.next().ok_or(Error::MissingValue)?;
                 Ok(Protocol::Ipcidr(s.parse()?))
             }
             "garlic64" => {
                 let s = iter
                     .next()
-                    .ok_or(Error::InvalidProtocolString)?
+                    .ok_or(Error::MissingValue)?
                     .replace('-', "+")
                     .replace('~', "/");
 
@@ -263,7 +550,7 @@ impl<'a> Protocol<'a> {
                 Ok(Protocol::Garlic64(Cow::from(decoded)))
             }
             "garlic32" => {
-                let s = iter.next().ok_or(Error::InvalidProtocolString)?;
+                let s = iter.next().ok_or(Error::MissingValue)?;
 
                 if s.len() < 55 && s.len() != 52 {
                     return Err(Error::InvalidProtocolString);
@@ -273,17 +560,65 @@ impl<'a> Protocol<'a> {
                 Ok(Protocol::Garlic32(Cow::from(decoded)))
             }
             "sni" => {
-                let s = iter.next().ok_or(Error::InvalidProtocolString)?;
+                let s = iter.next().ok_or(Error::MissingValue)?;
                 Ok(Protocol::Sni(Cow::Borrowed(s)))
             }
             "p2p-stardust" => Ok(Protocol::P2pStardust),
             "webrtc" => Ok(Protocol::WebRTC),
             "http-path" => {
-                let s = iter.next().ok_or(Error::InvalidProtocolString)?;
+                let s = iter.next().ok_or(Error::MissingValue)?;
                 let decoded = percent_encoding::percent_decode(s.as_bytes()).decode_utf8()?;
                 Ok(Protocol::HttpPath(decoded))
             }
-            unknown => Err(Error::UnknownProtocolString(unknown.to_string())),
+            "thread" => {
+                let s = iter.next().ok_or(Error::MissingValue)?;
+                let (_base, decoded) = multibase::decode(s)?;
+                Ok(Protocol::Thread(Cow::Owned(decoded)))
+            }
+            "shs" => {
+                let s = iter.next().ok_or(Error::MissingValue)?;
+                let (_base, decoded) = multibase::decode(s)?;
+                Ok(Protocol::Shs(Cow::Owned(decoded)))
+            }
+            "plaintextv2" => Ok(Protocol::PlaintextV2),
+            "scion" => {
+                let s = iter.next().ok_or(Error::MissingValue)?;
+                Ok(Protocol::Scion(Cow::Borrowed(s)))
+            }
+            "silverpine" => Ok(Protocol::Silverpine),
+            unknown => {
+                let custom = registry::by_name(unknown)
+                    .ok_or_else(|| Error::UnknownProtocolString(unknown.to_string()))?;
+                let value = match custom.codec {
+                    ValueCodec::None => Vec::new(),
+                    ValueCodec::Fixed(n) => {
+                        let s = iter.next().ok_or(Error::MissingValue)?;
+                        let decoded = HEXLOWER_PERMISSIVE
+                            .decode(s.as_bytes())
+                            .map_err(|e| Error::ParsingError(errors::boxed(e)))?;
+                        if decoded.len() != n {
+                            return Err(Error::InvalidProtocolString);
+                        }
+                        decoded
+                    }
+                    ValueCodec::LengthPrefixed => {
+                        let s = iter.next().ok_or(Error::MissingValue)?;
+                        percent_encoding::percent_decode(s.as_bytes()).collect()
+                    }
+                    ValueCodec::Path => {
+                        let path = read_path(iter)?;
+                        if !path.starts_with(b"/") {
+                            return Err(Error::InvalidProtocolString);
+                        }
+                        path
+                    }
+                };
+                Ok(Protocol::Custom {
+                    code: custom.code,
+                    name: custom.name,
+                    value: Cow::Owned(value),
+                })
+            }
         }
     }
 
@@ -300,8 +635,7 @@ impl<'a> Protocol<'a> {
         match id {
             DCCP => {
                 let (data, rest) = split_at(2, input)?;
-                let mut rdr = Cursor::new(data);
-                let num = rdr.read_u16::<BigEndian>()?;
+                let num = BigEndian::read_u16(data);
                 Ok((Protocol::Dccp(num), rest))
             }
             DNS => {
@@ -338,12 +672,8 @@ impl<'a> Protocol<'a> {
             }
             IP6 => {
                 let (data, rest) = split_at(16, input)?;
-                let mut rdr = Cursor::new(data);
                 let mut seg = [0_u16; 8];
-
-                for x in seg.iter_mut() {
-                    *x = rdr.read_u16::<BigEndian>()?;
-                }
+                BigEndian::read_u16_into(data, &mut seg);
 
                 let addr = Ipv6Addr::new(
                     seg[0], seg[1], seg[2], seg[3], seg[4], seg[5], seg[6], seg[7],
@@ -357,13 +687,14 @@ impl<'a> Protocol<'a> {
             CERTHASH => {
                 let (n, input) = decode::usize(input)?;
                 let (data, rest) = split_at(n, input)?;
-                Ok((Protocol::Certhash(Multihash::from_bytes(data)?), rest))
+                let hash =
+                    Multihash::from_bytes(data).map_err(|e| Error::InvalidMultihash(e.into()))?;
+                Ok((Protocol::Certhash(hash), rest))
             }
             P2P_WEBSOCKET_STAR => Ok((Protocol::P2pWebSocketStar, input)),
             MEMORY => {
                 let (data, rest) = split_at(8, input)?;
-                let mut rdr = Cursor::new(data);
-                let num = rdr.read_u64::<BigEndian>()?;
+                let num = BigEndian::read_u64(data);
                 Ok((Protocol::Memory(num), rest))
             }
             ONION => {
@@ -387,7 +718,7 @@ impl<'a> Protocol<'a> {
                 let (data, rest) = split_at(n, input)?;
                 Ok((
                     Protocol::P2p(
-                        PeerId::from_bytes(data).map_err(|e| Error::ParsingError(Box::new(e)))?,
+                        PeerId::from_bytes(data).map_err(|e| Error::InvalidPeerId(Box::new(e)))?,
                     ),
                     rest,
                 ))
@@ -397,22 +728,19 @@ impl<'a> Protocol<'a> {
             QUIC_V1 => Ok((Protocol::QuicV1, input)),
             SCTP => {
                 let (data, rest) = split_at(2, input)?;
-                let mut rdr = Cursor::new(data);
-                let num = rdr.read_u16::<BigEndian>()?;
+                let num = BigEndian::read_u16(data);
                 Ok((Protocol::Sctp(num), rest))
             }
             TCP => {
                 let (data, rest) = split_at(2, input)?;
-                let mut rdr = Cursor::new(data);
-                let num = rdr.read_u16::<BigEndian>()?;
+                let num = BigEndian::read_u16(data);
                 Ok((Protocol::Tcp(num), rest))
             }
             TLS => Ok((Protocol::Tls, input)),
             NOISE => Ok((Protocol::Noise, input)),
             UDP => {
                 let (data, rest) = split_at(2, input)?;
-                let mut rdr = Cursor::new(data);
-                let num = rdr.read_u16::<BigEndian>()?;
+                let num = BigEndian::read_u16(data);
                 Ok((Protocol::Udp(num), rest))
             }
             UDT => Ok((Protocol::Udt, input)),
@@ -472,13 +800,56 @@ impl<'a> Protocol<'a> {
                     rest,
                 ))
             }
-            _ => Err(Error::UnknownProtocolId(id)),
+            THREAD => {
+                let (n, input) = decode::usize(input)?;
+                let (data, rest) = split_at(n, input)?;
+                Ok((Protocol::Thread(Cow::Borrowed(data)), rest))
+            }
+            SHS => {
+                let (n, input) = decode::usize(input)?;
+                let (data, rest) = split_at(n, input)?;
+                Ok((Protocol::Shs(Cow::Borrowed(data)), rest))
+            }
+            PLAINTEXTV2 => Ok((Protocol::PlaintextV2, input)),
+            SCION => {
+                let (n, input) = decode::usize(input)?;
+                let (data, rest) = split_at(n, input)?;
+                Ok((Protocol::Scion(Cow::Borrowed(str::from_utf8(data)?)), rest))
+            }
+            SILVERPINE => Ok((Protocol::Silverpine, input)),
+            _ => {
+                let custom = registry::by_code(id).ok_or(Error::UnknownProtocolId(id))?;
+                let (value, rest) = match custom.codec {
+                    ValueCodec::None => (&input[..0], input),
+                    ValueCodec::Fixed(n) => split_at(n, input)?,
+                    ValueCodec::LengthPrefixed => {
+                        let (n, input) = decode::usize(input)?;
+                        split_at(n, input)?
+                    }
+                    ValueCodec::Path => {
+                        let (n, input) = decode::usize(input)?;
+                        let (data, rest) = split_at(n, input)?;
+                        if !data.starts_with(b"/") {
+                            return Err(Error::InvalidMultiaddr);
+                        }
+                        (data, rest)
+                    }
+                };
+                Ok((
+                    Protocol::Custom {
+                        code: custom.code,
+                        name: custom.name,
+                        value: Cow::Borrowed(value),
+                    },
+                    rest,
+                ))
+            }
         }
     }
 
     /// Encode this protocol by writing its binary representation into
-    /// the given `Write` impl.
-    pub fn write_bytes<W: Write>(&self, w: &mut W) -> Result<()> {
+    /// the given [`Writer`].
+    pub fn write_bytes<W: Writer + ?Sized>(&self, w: &mut W) -> Result<()> {
         let mut buf = encode::u32_buffer();
         match self {
             Protocol::Ip4(addr) => {
@@ -488,26 +859,26 @@ impl<'a> Protocol<'a> {
             Protocol::Ip6(addr) => {
                 w.write_all(encode::u32(IP6, &mut buf))?;
                 for &segment in &addr.segments() {
-                    w.write_u16::<BigEndian>(segment)?
+                    w.write_all(&segment.to_be_bytes())?
                 }
             }
             Protocol::Tcp(port) => {
                 w.write_all(encode::u32(TCP, &mut buf))?;
-                w.write_u16::<BigEndian>(*port)?
+                w.write_all(&port.to_be_bytes())?
             }
             Protocol::Tls => w.write_all(encode::u32(TLS, &mut buf))?,
             Protocol::Noise => w.write_all(encode::u32(NOISE, &mut buf))?,
             Protocol::Udp(port) => {
                 w.write_all(encode::u32(UDP, &mut buf))?;
-                w.write_u16::<BigEndian>(*port)?
+                w.write_all(&port.to_be_bytes())?
             }
             Protocol::Dccp(port) => {
                 w.write_all(encode::u32(DCCP, &mut buf))?;
-                w.write_u16::<BigEndian>(*port)?
+                w.write_all(&port.to_be_bytes())?
             }
             Protocol::Sctp(port) => {
                 w.write_all(encode::u32(SCTP, &mut buf))?;
-                w.write_u16::<BigEndian>(*port)?
+                w.write_all(&port.to_be_bytes())?
             }
             Protocol::Dns(s) => {
                 w.write_all(encode::u32(DNS, &mut buf))?;
@@ -548,12 +919,12 @@ impl<'a> Protocol<'a> {
             Protocol::Onion(addr, port) => {
                 w.write_all(encode::u32(ONION, &mut buf))?;
                 w.write_all(addr.as_ref())?;
-                w.write_u16::<BigEndian>(*port)?
+                w.write_all(&port.to_be_bytes())?
             }
             Protocol::Onion3(addr) => {
                 w.write_all(encode::u32(ONION3, &mut buf))?;
                 w.write_all(addr.hash().as_ref())?;
-                w.write_u16::<BigEndian>(addr.port())?
+                w.write_all(&addr.port().to_be_bytes())?
             }
             Protocol::Quic => w.write_all(encode::u32(QUIC, &mut buf))?,
             Protocol::QuicV1 => w.write_all(encode::u32(QUIC_V1, &mut buf))?,
@@ -589,7 +960,7 @@ impl<'a> Protocol<'a> {
             Protocol::P2pCircuit => w.write_all(encode::u32(P2P_CIRCUIT, &mut buf))?,
             Protocol::Memory(port) => {
                 w.write_all(encode::u32(MEMORY, &mut buf))?;
-                w.write_u64::<BigEndian>(*port)?
+                w.write_all(&port.to_be_bytes())?
             }
             Protocol::Ip6zone(zone_id) => {
                 w.write_all(encode::u32(IP6ZONE, &mut buf))?;
@@ -599,7 +970,7 @@ impl<'a> Protocol<'a> {
             }
             Protocol::Ipcidr(mask) => {
                 w.write_all(encode::u32(IPCIDR, &mut buf))?;
-                w.write_u8(*mask)?
+                w.write_all(&[*mask])?
             }
             Protocol::Garlic64(addr) => {
                 w.write_all(encode::u32(GARLIC64, &mut buf))?;
@@ -625,6 +996,39 @@ impl<'a> Protocol<'a> {
                 w.write_all(encode::usize(bytes.len(), &mut encode::usize_buffer()))?;
                 w.write_all(bytes)?
             }
+            Protocol::Thread(id) => {
+                w.write_all(encode::u32(THREAD, &mut buf))?;
+                w.write_all(encode::usize(id.len(), &mut encode::usize_buffer()))?;
+                w.write_all(id)?
+            }
+            Protocol::Shs(key) => {
+                w.write_all(encode::u32(SHS, &mut buf))?;
+                w.write_all(encode::usize(key.len(), &mut encode::usize_buffer()))?;
+                w.write_all(key)?
+            }
+            Protocol::PlaintextV2 => w.write_all(encode::u32(PLAINTEXTV2, &mut buf))?,
+            Protocol::Scion(s) => {
+                w.write_all(encode::u32(SCION, &mut buf))?;
+                let bytes = s.as_bytes();
+                w.write_all(encode::usize(bytes.len(), &mut encode::usize_buffer()))?;
+                w.write_all(bytes)?
+            }
+            Protocol::Silverpine => w.write_all(encode::u32(SILVERPINE, &mut buf))?,
+            Protocol::Custom { code, value, .. } => {
+                let custom = registry::by_code(*code).ok_or(Error::UnknownProtocolId(*code))?;
+                match custom.codec {
+                    ValueCodec::None if value.is_empty() => {}
+                    ValueCodec::Fixed(n) if value.len() == n => {}
+                    ValueCodec::LengthPrefixed => {}
+                    ValueCodec::Path if value.starts_with(b"/") => {}
+                    _ => return Err(Error::InvalidMultiaddr),
+                }
+                w.write_all(encode::u32(*code, &mut buf))?;
+                if let ValueCodec::LengthPrefixed | ValueCodec::Path = custom.codec {
+                    w.write_all(encode::usize(value.len(), &mut encode::usize_buffer()))?;
+                }
+                w.write_all(value)?
+            }
         }
         Ok(())
     }
@@ -673,56 +1077,127 @@ impl<'a> Protocol<'a> {
             P2pStardust => P2pStardust,
             WebRTC => WebRTC,
             HttpPath(cow) => HttpPath(Cow::Owned(cow.into_owned())),
+            Thread(id) => Thread(Cow::Owned(id.into_owned())),
+            Shs(key) => Shs(Cow::Owned(key.into_owned())),
+            PlaintextV2 => PlaintextV2,
+            Scion(cow) => Scion(Cow::Owned(cow.into_owned())),
+            Silverpine => Silverpine,
+            Custom { code, name, value } => Custom {
+                code,
+                name,
+                value: Cow::Owned(value.into_owned()),
+            },
         }
     }
 
-    pub fn tag(&self) -> &'static str {
+    /// Returns the layer of the network stack this protocol belongs to.
+    ///
+    /// The deprecated `/https` and `/wss` are classified by their outermost layer,
+    /// i.e. as [`Layer::Application`] and [`Layer::Transport`] respectively.
+    pub fn layer(&self) -> Layer {
         use self::Protocol::*;
         match self {
-            Dccp(_) => "dccp",
-            Dns(_) => "dns",
-            Dns4(_) => "dns4",
-            Dns6(_) => "dns6",
-            Dnsaddr(_) => "dnsaddr",
-            Http => "http",
-            Https => "https",
-            Ip4(_) => "ip4",
-            Ip6(_) => "ip6",
-            P2pWebRtcDirect => "p2p-webrtc-direct",
-            P2pWebRtcStar => "p2p-webrtc-star",
-            WebRTCDirect => "webrtc-direct",
-            Certhash(_) => "certhash",
-            P2pWebSocketStar => "p2p-websocket-star",
-            Memory(_) => "memory",
-            Onion(_, _) => "onion",
-            Onion3(_) => "onion3",
-            P2p(_) => "p2p",
-            P2pCircuit => "p2p-circuit",
-            Quic => "quic",
-            QuicV1 => "quic-v1",
-            Sctp(_) => "sctp",
-            Tcp(_) => "tcp",
-            Tls => "tls",
-            Noise => "noise",
-            Udp(_) => "udp",
-            Udt => "udt",
-            Unix(_) => "unix",
-            Utp => "utp",
-            WebTransport => "webtransport",
-            Ws(ref s) if s == "/" => "ws",
-            Ws(_) => "x-parity-ws",
-            Wss(ref s) if s == "/" => "wss",
-            Wss(_) => "x-parity-wss",
-            Ip6zone(_) => "ip6zone",
-            Ipcidr(_) => "ipcidr",
-            Garlic64(_) => "garlic64",
-            Garlic32(_) => "garlic32",
-            Sni(_) => "sni",
-            P2pStardust => "p2p-stardust",
-            WebRTC => "webrtc",
-            HttpPath(_) => "http-path",
+            Dns(_)
+            | Dns4(_)
+            | Dns6(_)
+            | Dnsaddr(_)
+            | Ip4(_)
+            | Ip6(_)
+            | Ip6zone(_)
+            | Ipcidr(_)
+            | Onion(_, _)
+            | Onion3(_)
+            | Garlic64(_)
+            | Garlic32(_)
+            | Scion(_) => Layer::Network,
+            Dccp(_) | Sctp(_) | Tcp(_) | Udp(_) | Udt | Utp | Quic | QuicV1 | WebTransport
+            | WebRTCDirect | WebRTC | Ws(_) | Wss(_) | Memory(_) | P2pWebRtcDirect
+            | P2pWebRtcStar | P2pWebSocketStar | P2pStardust | Silverpine => Layer::Transport,
+            Tls | Noise | Sni(_) | Certhash(_) | Shs(_) | PlaintextV2 => Layer::Security,
+            Http | Https | Thread(_) => Layer::Application,
+            P2pCircuit => Layer::Relay,
+            P2p(_) => Layer::PeerIdentity,
+            Unix(_) | HttpPath(_) => Layer::Path,
+            Custom { .. } => Layer::Unknown,
         }
     }
+
+    /// Returns the kind of this protocol, or `None` for a [`Protocol::Custom`].
+    ///
+    /// `/ws` and `/wss` with a path other than `/` are of the kinds
+    /// [`ProtocolKind::XParityWs`] and [`ProtocolKind::XParityWss`] respectively.
+    pub fn kind(&self) -> Option<ProtocolKind> {
+        self.builtin_kind().ok()
+    }
+
+    /// Returns the kind of this protocol, or the code and name of a [`Protocol::Custom`].
+    fn builtin_kind(&self) -> core::result::Result<ProtocolKind, (u32, &'static str)> {
+        use self::Protocol::*;
+        Ok(match self {
+            Dccp(_) => ProtocolKind::Dccp,
+            Dns(_) => ProtocolKind::Dns,
+            Dns4(_) => ProtocolKind::Dns4,
+            Dns6(_) => ProtocolKind::Dns6,
+            Dnsaddr(_) => ProtocolKind::Dnsaddr,
+            Http => ProtocolKind::Http,
+            Https => ProtocolKind::Https,
+            Ip4(_) => ProtocolKind::Ip4,
+            Ip6(_) => ProtocolKind::Ip6,
+            P2pWebRtcDirect => ProtocolKind::P2pWebRtcDirect,
+            P2pWebRtcStar => ProtocolKind::P2pWebRtcStar,
+            WebRTCDirect => ProtocolKind::WebRTCDirect,
+            Certhash(_) => ProtocolKind::Certhash,
+            P2pWebSocketStar => ProtocolKind::P2pWebSocketStar,
+            Memory(_) => ProtocolKind::Memory,
+            Onion(_, _) => ProtocolKind::Onion,
+            Onion3(_) => ProtocolKind::Onion3,
+            P2p(_) => ProtocolKind::P2p,
+            P2pCircuit => ProtocolKind::P2pCircuit,
+            Quic => ProtocolKind::Quic,
+            QuicV1 => ProtocolKind::QuicV1,
+            Sctp(_) => ProtocolKind::Sctp,
+            Tcp(_) => ProtocolKind::Tcp,
+            Tls => ProtocolKind::Tls,
+            Noise => ProtocolKind::Noise,
+            Udp(_) => ProtocolKind::Udp,
+            Udt => ProtocolKind::Udt,
+            Unix(_) => ProtocolKind::Unix,
+            Utp => ProtocolKind::Utp,
+            WebTransport => ProtocolKind::WebTransport,
+            Ws(ref s) if s == "/" => ProtocolKind::Ws,
+            Ws(_) => ProtocolKind::XParityWs,
+            Wss(ref s) if s == "/" => ProtocolKind::Wss,
+            Wss(_) => ProtocolKind::XParityWss,
+            Ip6zone(_) => ProtocolKind::Ip6zone,
+            Ipcidr(_) => ProtocolKind::Ipcidr,
+            Garlic64(_) => ProtocolKind::Garlic64,
+            Garlic32(_) => ProtocolKind::Garlic32,
+            Sni(_) => ProtocolKind::Sni,
+            P2pStardust => ProtocolKind::P2pStardust,
+            WebRTC => ProtocolKind::WebRTC,
+            HttpPath(_) => ProtocolKind::HttpPath,
+            Thread(_) => ProtocolKind::Thread,
+            Shs(_) => ProtocolKind::Shs,
+            PlaintextV2 => ProtocolKind::PlaintextV2,
+            Scion(_) => ProtocolKind::Scion,
+            Silverpine => ProtocolKind::Silverpine,
+            Custom { code, name, .. } => return Err((*code, name)),
+        })
+    }
+
+    /// Returns the multicodec this protocol is encoded with.
+    ///
+    /// `/ws` and `/wss` with a path other than `/` are encoded as `x-parity-ws` and
+    /// `x-parity-wss` respectively, and return their codes.
+    pub fn code(&self) -> u32 {
+        self.builtin_kind()
+            .map_or_else(|(code, _)| code, ProtocolKind::code)
+    }
+
+    pub fn tag(&self) -> &'static str {
+        self.builtin_kind()
+            .map_or_else(|(_, name)| name, ProtocolKind::name)
+    }
 }
 
 impl fmt::Display for Protocol<'_> {
@@ -751,17 +1226,12 @@ impl fmt::Display for Protocol<'_> {
                 let s = BASE32.encode(addr.hash());
                 write!(f, "/{}:{}", s.to_lowercase(), addr.port())
             }
-            P2p(c) => write!(f, "/{}", multibase::Base::Base58Btc.encode(c.to_bytes())),
+            P2p(c) => write!(f, "/{c}"),
             Sctp(port) => write!(f, "/{port}"),
             Tcp(port) => write!(f, "/{port}"),
             Udp(port) => write!(f, "/{port}"),
-            Unix(s) => write!(f, "/{s}"),
-            Ws(s) if s != "/" => {
-                let encoded =
-                    percent_encoding::percent_encode(s.as_bytes(), PATH_SEGMENT_ENCODE_SET);
-                write!(f, "/{encoded}")
-            }
-            Wss(s) if s != "/" => {
+            Unix(s) => write_path(f, s.as_bytes()),
+            Ws(s) | Wss(s) if s != "/" => {
                 let encoded =
                     percent_encoding::percent_encode(s.as_bytes(), PATH_SEGMENT_ENCODE_SET);
                 write!(f, "/{encoded}")
@@ -783,6 +1253,22 @@ impl fmt::Display for Protocol<'_> {
                     percent_encoding::percent_encode(s.as_bytes(), PATH_SEGMENT_ENCODE_SET);
                 write!(f, "/{encoded}")
             }
+            Thread(id) => write!(
+                f,
+                "/{}",
+                multibase::encode(multibase::Base::Base32Lower, id)
+            ),
+            Shs(key) => write!(f, "/{}", multibase::encode(multibase::Base::Base64Url, key)),
+            Scion(s) => write!(f, "/{s}"),
+            Custom { code, value, .. } => match registry::by_code(*code).map(|p| p.codec) {
+                Some(ValueCodec::Fixed(_)) => write!(f, "/{}", HEXLOWER.encode(value)),
+                Some(ValueCodec::LengthPrefixed) => {
+                    let encoded = percent_encoding::percent_encode(value, PATH_SEGMENT_ENCODE_SET);
+                    write!(f, "/{encoded}")
+                }
+                Some(ValueCodec::Path) => write_path(f, value),
+                Some(ValueCodec::None) | None => Ok(()),
+            },
             _ => Ok(()),
         }
     }
@@ -812,6 +1298,37 @@ impl From<Ipv6Addr> for Protocol<'_> {
     }
 }
 
+/// Parses the value of a path protocol such as `/unix` from a single percent-encoded segment,
+/// see [`write_path`].
+fn read_path<'a, I>(mut iter: I) -> Result<Vec<u8>>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let s = iter.next().ok_or(Error::MissingValue)?;
+    Ok(percent_encoding::percent_decode(s.as_bytes()).collect())
+}
+
+/// Writes the value of a path protocol as a single segment, so that it may be followed by
+/// other components.
+///
+/// Only `/`, `%` and bytes that are not valid UTF-8 are percent-encoded.
+fn write_path(f: &mut fmt::Formatter<'_>, path: &[u8]) -> fmt::Result {
+    f.write_str("/")?;
+    for chunk in path.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '/' => f.write_str("%2F")?,
+                '%' => f.write_str("%25")?,
+                c => write!(f, "{c}")?,
+            }
+        }
+        for b in chunk.invalid() {
+            write!(f, "%{b:02X}")?;
+        }
+    }
+    Ok(())
+}
+
 macro_rules! read_onion_impl {
     ($name:ident, $len:expr, $encoded_len:expr) => {
         fn $name(s: &str) -> Result<([u8; $len], u16)> {
@@ -0,0 +1,147 @@
+#[cfg(feature = "std")]
+use crate::protocol;
+use core::{error, fmt};
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+
+/// Without the `std` feature there is no registry, so no protocol is ever registered.
+#[cfg(feature = "std")]
+static REGISTRY: RwLock<Vec<CustomProtocol>> = RwLock::new(Vec::new());
+
+/// How the value of a [`CustomProtocol`] is encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueCodec {
+    /// The protocol carries no value, e.g. `/quic-v1`.
+    None,
+    /// The value is exactly this many bytes long.
+    ///
+    /// The string representation is lowercase hex.
+    Fixed(usize),
+    /// The value is prefixed by its varint-encoded length.
+    ///
+    /// The string representation is the percent-encoded value.
+    LengthPrefixed,
+    /// Like [`ValueCodec::LengthPrefixed`], but the value is a path starting with `/`.
+    ///
+    /// Like for `/unix`, the string representation is a single segment in which `/` and `%`
+    /// are percent-encoded, e.g. `/unix/%2Ftmp%2Fnode.sock`.
+    Path,
+}
+
+/// A protocol registered at runtime, see [`register_protocol`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CustomProtocol {
+    /// The multicodec of the protocol.
+    pub code: u32,
+    /// The name of the protocol in the string representation of a multiaddress.
+    pub name: &'static str,
+    /// How the value of the protocol is encoded.
+    pub codec: ValueCodec,
+}
+
+/// Registers a protocol that is not known to this crate.
+///
+/// Once registered, the protocol is decoded into [`Protocol::Custom`](crate::Protocol::Custom)
+/// from both the binary and the string representation of a multiaddress, for the remaining
+/// lifetime of the process. Protocols cannot be unregistered, as that would invalidate
+/// existing multiaddresses.
+///
+/// Registering the exact same protocol twice is a no-op.
+///
+/// # Example
+///
+/// ```
+/// use multiaddr::{register_protocol, CustomProtocol, Multiaddr, Protocol, ValueCodec};
+///
+/// register_protocol(CustomProtocol {
+///     code: 0x300000,
+///     name: "my-transport",
+///     codec: ValueCodec::LengthPrefixed,
+/// })
+/// .unwrap();
+///
+/// let addr: Multiaddr = "/ip4/127.0.0.1/my-transport/hello".parse().unwrap();
+/// assert_eq!(
+///     addr.iter().nth(1).unwrap(),
+///     Protocol::Custom {
+///         code: 0x300000,
+///         name: "my-transport",
+///         value: b"hello"[..].into(),
+///     }
+/// );
+/// ```
+///
+#[cfg(feature = "std")]
+pub fn register_protocol(protocol: CustomProtocol) -> Result<(), RegisterError> {
+    if protocol::is_builtin_code(protocol.code) {
+        return Err(RegisterError::CodeInUse(protocol.code));
+    }
+    if protocol.name.is_empty() || protocol.name.chars().any(|c| c == '/' || c.is_whitespace()) {
+        return Err(RegisterError::InvalidName(protocol.name));
+    }
+    if protocol::is_builtin_name(protocol.name) {
+        return Err(RegisterError::NameInUse(protocol.name));
+    }
+
+    let mut registry = REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+    for p in registry.iter() {
+        if *p == protocol {
+            return Ok(());
+        }
+        if p.code == protocol.code {
+            return Err(RegisterError::CodeInUse(protocol.code));
+        }
+        if p.name == protocol.name {
+            return Err(RegisterError::NameInUse(protocol.name));
+        }
+    }
+    registry.push(protocol);
+    Ok(())
+}
+
+/// Looks up a registered protocol by its code.
+pub(crate) fn by_code(code: u32) -> Option<CustomProtocol> {
+    find(|p| p.code == code)
+}
+
+/// Looks up a registered protocol by its name.
+pub(crate) fn by_name(name: &str) -> Option<CustomProtocol> {
+    find(|p| p.name == name)
+}
+
+#[cfg(feature = "std")]
+fn find(f: impl Fn(&CustomProtocol) -> bool) -> Option<CustomProtocol> {
+    let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+    registry.iter().copied().find(f)
+}
+
+#[cfg(not(feature = "std"))]
+fn find(_: impl Fn(&CustomProtocol) -> bool) -> Option<CustomProtocol> {
+    None
+}
+
+/// Error while registering a [`CustomProtocol`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegisterError {
+    /// The code is already used by a built-in or a previously registered protocol.
+    CodeInUse(u32),
+    /// The name is already used by a built-in or a previously registered protocol.
+    NameInUse(&'static str),
+    /// The name is empty or contains `/` or whitespace, so it cannot be parsed from the string
+    /// representation of a multiaddress.
+    InvalidName(&'static str),
+}
+
+impl fmt::Display for RegisterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegisterError::CodeInUse(code) => write!(f, "protocol code {code} is already in use"),
+            RegisterError::NameInUse(name) => {
+                write!(f, "protocol name `{name}` is already in use")
+            }
+            RegisterError::InvalidName(name) => write!(f, "invalid protocol name `{name}`"),
+        }
+    }
+}
+
+impl error::Error for RegisterError {}
@@ -0,0 +1,311 @@
+use crate::{Multiaddr, MultiaddrSlice, PeerId, Protocol, ProtocolKind};
+use alloc::{borrow::ToOwned, vec, vec::Vec};
+use core::{convert::TryFrom, error, fmt};
+
+/// A multiaddress of a peer reached through one or more circuit relays.
+///
+/// A relayed address has the form
+/// `<relay address>/p2p/<relay>/p2p-circuit[/p2p/<relay>/p2p-circuit...][/webrtc][/p2p/<destination>]`.
+/// The first relay is dialed at the relay address, every further relay is reached through
+/// a circuit over the previous one. The relay address may be empty, if the first relay is
+/// to be dialed by its peer id alone. The connection to the destination may be upgraded to a
+/// direct `/webrtc` connection, using the circuit for signalling only.
+///
+/// # Example
+///
+/// ```
+/// use multiaddr::{Multiaddr, RelayedAddr};
+/// use std::convert::TryFrom;
+///
+/// let addr: Multiaddr = "/ip4/1.2.3.4/tcp/80/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit/p2p/12D3KooWLRPJAA5o6Z6hDbVvG3WqCykmtgPufEAPvmRzjXXBfLeP"
+///     .parse()
+///     .unwrap();
+/// let relayed = RelayedAddr::try_from(&addr).unwrap();
+/// assert_eq!(relayed.relay_addr().to_string(), "/ip4/1.2.3.4/tcp/80");
+/// assert_eq!(relayed.relay().to_string(), "QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC");
+/// assert_eq!(
+///     relayed.destination().unwrap().to_string(),
+///     "12D3KooWLRPJAA5o6Z6hDbVvG3WqCykmtgPufEAPvmRzjXXBfLeP"
+/// );
+/// assert_eq!(Multiaddr::from(relayed), addr);
+/// ```
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RelayedAddr {
+    relay_addr: Multiaddr,
+    /// Never empty.
+    relays: Vec<PeerId>,
+    /// The upgrades following the last `/p2p-circuit`, see [`CIRCUIT_UPGRADES`].
+    upgrades: Multiaddr,
+    destination: Option<PeerId>,
+}
+
+/// The protocols that may follow the last `/p2p-circuit`, upgrading the relayed connection,
+/// as accepted by go-libp2p.
+const CIRCUIT_UPGRADES: &[ProtocolKind] = &[ProtocolKind::WebRTC];
+
+fn is_circuit_upgrade(p: &Protocol<'_>) -> bool {
+    p.kind()
+        .is_some_and(|kind| CIRCUIT_UPGRADES.contains(&kind))
+}
+
+impl RelayedAddr {
+    /// Starts building a relayed address through the relay reachable at `relay`, which
+    /// must end in the relay's `/p2p` component.
+    pub fn builder(relay: Multiaddr) -> RelayedAddrBuilder {
+        RelayedAddrBuilder {
+            relay,
+            via: Vec::new(),
+            upgrades: Multiaddr::empty(),
+            destination: None,
+        }
+    }
+
+    /// Returns the address the first relay is dialed at, without its `/p2p` component.
+    pub fn relay_addr(&self) -> &MultiaddrSlice {
+        &self.relay_addr
+    }
+
+    /// Returns the peer id of the first relay.
+    pub fn relay(&self) -> &PeerId {
+        &self.relays[0]
+    }
+
+    /// Returns the peer ids of all relays, starting with the one dialed at
+    /// [`relay_addr`](RelayedAddr::relay_addr).
+    pub fn relays(&self) -> &[PeerId] {
+        &self.relays
+    }
+
+    /// Returns the upgrades of the connection to the destination following the last
+    /// `/p2p-circuit`, e.g. `/webrtc`, or an empty multiaddress if there are none.
+    pub fn upgrades(&self) -> &MultiaddrSlice {
+        &self.upgrades
+    }
+
+    /// Returns the peer id of the destination, if the address names one.
+    pub fn destination(&self) -> Option<&PeerId> {
+        self.destination.as_ref()
+    }
+
+    /// Returns the multiaddress of this relayed address.
+    pub fn to_multiaddr(&self) -> Multiaddr {
+        let mut addr = self.relay_addr.clone();
+        for relay in &self.relays {
+            addr.push(Protocol::P2p(*relay));
+            addr.push(Protocol::P2pCircuit);
+        }
+        addr = addr.encapsulate(&self.upgrades);
+        if let Some(destination) = self.destination {
+            addr.push(Protocol::P2p(destination));
+        }
+        addr
+    }
+}
+
+impl TryFrom<&MultiaddrSlice> for RelayedAddr {
+    type Error = RelayedAddrErr;
+
+    fn try_from(addr: &MultiaddrSlice) -> Result<Self, Self::Error> {
+        let (before, _, rest) = addr
+            .split_first(ProtocolKind::P2pCircuit)
+            .ok_or(RelayedAddrErr::NotRelayed)?;
+        let index = before.iter().count();
+        let mut relay_addr = before.to_owned();
+        let relay = match relay_addr.pop() {
+            Some(Protocol::P2p(relay)) => relay,
+            _ => return Err(RelayedAddrErr::MissingRelay { index }),
+        };
+
+        let mut relays = vec![relay];
+        let mut upgrades = Multiaddr::empty();
+        let mut destination = None;
+        let mut iter = rest
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (index + 1 + i, p))
+            .peekable();
+        // The index of the first upgrade, after which no further relay may follow.
+        let mut upgraded = None;
+        loop {
+            while let Some((i, p)) = iter.next_if(|(_, p)| is_circuit_upgrade(p)) {
+                upgraded = upgraded.or(Some((i, p.tag())));
+                upgrades.push(p);
+            }
+            let Some((i, p)) = iter.next() else {
+                break;
+            };
+            let peer = match p {
+                Protocol::P2p(peer) => peer,
+                Protocol::P2pCircuit => return Err(RelayedAddrErr::MissingRelay { index: i }),
+                p => return Err(RelayedAddrErr::unexpected(i, &p)),
+            };
+            match (iter.next(), upgraded) {
+                (None, _) => destination = Some(peer),
+                (Some((_, Protocol::P2pCircuit)), None) => relays.push(peer),
+                (Some((_, Protocol::P2pCircuit)), Some((index, tag))) => {
+                    return Err(RelayedAddrErr::UnexpectedProtocol { index, tag })
+                }
+                (Some((i, p)), _) => return Err(RelayedAddrErr::unexpected(i, &p)),
+            }
+        }
+
+        Ok(RelayedAddr {
+            relay_addr,
+            relays,
+            upgrades,
+            destination,
+        })
+    }
+}
+
+impl TryFrom<&Multiaddr> for RelayedAddr {
+    type Error = RelayedAddrErr;
+
+    fn try_from(addr: &Multiaddr) -> Result<Self, Self::Error> {
+        RelayedAddr::try_from(&**addr)
+    }
+}
+
+impl From<RelayedAddr> for Multiaddr {
+    fn from(addr: RelayedAddr) -> Multiaddr {
+        addr.to_multiaddr()
+    }
+}
+
+impl fmt::Display for RelayedAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.to_multiaddr(), f)
+    }
+}
+
+/// Builder for a [`RelayedAddr`], see [`RelayedAddr::builder`].
+///
+/// # Example
+///
+/// ```
+/// use multiaddr::{Multiaddr, PeerId, RelayedAddr};
+///
+/// let relay: Multiaddr = "/ip4/1.2.3.4/tcp/80/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC"
+///     .parse()
+///     .unwrap();
+/// let destination: PeerId = "12D3KooWLRPJAA5o6Z6hDbVvG3WqCykmtgPufEAPvmRzjXXBfLeP".parse().unwrap();
+///
+/// let relayed = RelayedAddr::builder(relay).destination(destination).build().unwrap();
+/// assert_eq!(
+///     relayed.to_string(),
+///     "/ip4/1.2.3.4/tcp/80/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit/p2p/12D3KooWLRPJAA5o6Z6hDbVvG3WqCykmtgPufEAPvmRzjXXBfLeP"
+/// );
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct RelayedAddrBuilder {
+    relay: Multiaddr,
+    via: Vec<PeerId>,
+    upgrades: Multiaddr,
+    destination: Option<PeerId>,
+}
+
+impl RelayedAddrBuilder {
+    /// Adds a further relay, reached through a circuit over the previous one.
+    pub fn via(mut self, relay: PeerId) -> Self {
+        self.via.push(relay);
+        self
+    }
+
+    /// Adds an upgrade of the connection to the destination, e.g. [`Protocol::WebRTC`].
+    pub fn upgrade(mut self, upgrade: Protocol<'_>) -> Self {
+        self.upgrades.push(upgrade);
+        self
+    }
+
+    /// Sets the peer id of the destination.
+    pub fn destination(mut self, destination: PeerId) -> Self {
+        self.destination = Some(destination);
+        self
+    }
+
+    /// Builds the relayed address.
+    ///
+    /// Fails if the relay address does not end in `/p2p` or contains a `/p2p-circuit`, or if
+    /// an upgrade is not one that may follow a `/p2p-circuit`.
+    pub fn build(self) -> Result<RelayedAddr, RelayedAddrErr> {
+        let mut relay_addr = self.relay;
+        if let Some((index, p)) = relay_addr
+            .iter()
+            .enumerate()
+            .find(|(_, p)| *p == Protocol::P2pCircuit)
+        {
+            return Err(RelayedAddrErr::unexpected(index, &p));
+        }
+        let index = relay_addr.iter().count();
+        let relay = match relay_addr.pop() {
+            Some(Protocol::P2p(relay)) => relay,
+            _ => return Err(RelayedAddrErr::MissingRelay { index }),
+        };
+        let mut relays = vec![relay];
+        relays.extend(self.via);
+        let index = index + 2 * relays.len() - 1;
+        if let Some((i, p)) = self
+            .upgrades
+            .iter()
+            .enumerate()
+            .find(|(_, p)| !is_circuit_upgrade(p))
+        {
+            return Err(RelayedAddrErr::unexpected(index + i, &p));
+        }
+        Ok(RelayedAddr {
+            relay_addr,
+            relays,
+            upgrades: self.upgrades,
+            destination: self.destination,
+        })
+    }
+}
+
+/// Error while turning a multiaddress into a [`RelayedAddr`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelayedAddrErr {
+    /// The multiaddress does not contain a `/p2p-circuit`.
+    NotRelayed,
+    /// The `/p2p-circuit` at `index` is not preceded by the relay's `/p2p`.
+    MissingRelay {
+        /// Index of the offending component.
+        index: usize,
+    },
+    /// The component at `index` cannot be part of a relayed address at its position.
+    UnexpectedProtocol {
+        /// Index of the offending component.
+        index: usize,
+        /// Tag of the offending component, see [`Protocol::tag`].
+        tag: &'static str,
+    },
+}
+
+impl RelayedAddrErr {
+    fn unexpected(index: usize, p: &Protocol<'_>) -> Self {
+        RelayedAddrErr::UnexpectedProtocol {
+            index,
+            tag: p.tag(),
+        }
+    }
+}
+
+impl fmt::Display for RelayedAddrErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RelayedAddrErr::NotRelayed => f.write_str("multiaddr does not contain a p2p-circuit"),
+            RelayedAddrErr::MissingRelay { index } => {
+                write!(
+                    f,
+                    "p2p-circuit at component {index} is not preceded by a relay peer id"
+                )
+            }
+            RelayedAddrErr::UnexpectedProtocol { index, tag } => {
+                write!(f, "unexpected protocol `{tag}` at component {index}")
+            }
+        }
+    }
+}
+
+impl error::Error for RelayedAddrErr {}
@@ -0,0 +1,391 @@
+use crate::{dnsaddr, Multiaddr, MultiaddrSlice, Protocol};
+use std::{
+    collections::{HashMap, VecDeque},
+    error, fmt,
+    future::Future,
+    io,
+    net::IpAddr,
+    pin::Pin,
+};
+
+/// The maximum number of nested `/dnsaddr` lookups performed while resolving.
+pub const MAX_DNSADDR_DEPTH: usize = 8;
+
+/// The maximum number of DNS lookups performed while resolving a single multiaddress.
+pub const MAX_LOOKUPS: usize = 64;
+
+/// The maximum number of multiaddresses a single multiaddress is resolved into.
+pub const MAX_RESOLVED_ADDRS: usize = 100;
+
+/// A boxed future, as returned by [`AsyncResolver`].
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Performs the DNS lookups needed by [`MultiaddrSlice::resolve`].
+pub trait Resolver {
+    /// Returns the IPv4 and IPv6 addresses of `name`.
+    fn lookup_ip(&self, name: &str) -> io::Result<Vec<IpAddr>>;
+
+    /// Returns the TXT records of `name`.
+    fn lookup_txt(&self, name: &str) -> io::Result<Vec<String>>;
+}
+
+/// Performs the DNS lookups needed by [`MultiaddrSlice::resolve_async`].
+pub trait AsyncResolver {
+    /// Returns the IPv4 and IPv6 addresses of `name`.
+    fn lookup_ip<'a>(&'a self, name: &'a str) -> BoxFuture<'a, io::Result<Vec<IpAddr>>>;
+
+    /// Returns the TXT records of `name`.
+    fn lookup_txt<'a>(&'a self, name: &'a str) -> BoxFuture<'a, io::Result<Vec<String>>>;
+}
+
+impl MultiaddrSlice {
+    /// Expands the `/dns`, `/dns4`, `/dns6` and `/dnsaddr` components of this multiaddress
+    /// into concrete `/ip4` and `/ip6` addresses.
+    ///
+    /// `/dns4` and `/dns6` only expand to IPv4 and IPv6 addresses respectively, and the
+    /// components before and after the resolved one are kept.
+    ///
+    /// `/dnsaddr/<name>` is replaced by the multiaddresses in the `dnsaddr=<multiaddr>` TXT
+    /// records of `_dnsaddr.<name>`, which are resolved in turn, up to
+    /// [`MAX_DNSADDR_DEPTH`] levels deep. If `/dnsaddr/<name>` is followed by further
+    /// components, e.g. `/p2p/<peer>`, only records ending in these components are used.
+    /// Records that cannot be parsed, that refer back to a name currently being resolved, that
+    /// are nested too deeply, or whose own lookups fail are skipped. Only a failed lookup of a
+    /// name of this multiaddress itself is an error.
+    ///
+    /// As the records may come from untrusted DNS servers, at most [`MAX_LOOKUPS`] lookups
+    /// are performed, and at most [`MAX_RESOLVED_ADDRS`] multiaddresses are returned.
+    /// Addresses that would need further lookups are skipped once the former is reached, and
+    /// the resolution stops once the latter is reached.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use multiaddr::{MockResolver, Multiaddr};
+    ///
+    /// let mut resolver = MockResolver::new();
+    /// resolver.add_ip("example.com", "1.2.3.4".parse().unwrap());
+    /// resolver.add_ip("example.com", "::1".parse().unwrap());
+    /// resolver.add_txt("_dnsaddr.bootstrap.example.com", "dnsaddr=/dns4/example.com/tcp/4001");
+    ///
+    /// let addr: Multiaddr = "/dnsaddr/bootstrap.example.com".parse().unwrap();
+    /// assert_eq!(
+    ///     addr.resolve(&resolver).unwrap(),
+    ///     vec!["/ip4/1.2.3.4/tcp/4001".parse::<Multiaddr>().unwrap()]
+    /// );
+    /// ```
+    ///
+    pub fn resolve<R: Resolver>(&self, resolver: &R) -> Result<Vec<Multiaddr>, ResolveError> {
+        let mut expansion = Expansion::new(self);
+        while let Some(query) = expansion.next_query() {
+            let answer = match &query {
+                Query::Ip { name, .. } => resolver.lookup_ip(name).map(Answer::Ip),
+                Query::Txt { name, .. } => resolver.lookup_txt(name).map(Answer::Txt),
+            };
+            expansion.answer(query, answer)?;
+        }
+        Ok(expansion.resolved)
+    }
+
+    /// Like [`resolve`](MultiaddrSlice::resolve), but with an [`AsyncResolver`].
+    pub async fn resolve_async<R: AsyncResolver>(
+        &self,
+        resolver: &R,
+    ) -> Result<Vec<Multiaddr>, ResolveError> {
+        let mut expansion = Expansion::new(self);
+        while let Some(query) = expansion.next_query() {
+            let answer = match &query {
+                Query::Ip { name, .. } => resolver.lookup_ip(name).await.map(Answer::Ip),
+                Query::Txt { name, .. } => resolver.lookup_txt(name).await.map(Answer::Txt),
+            };
+            expansion.answer(query, answer)?;
+        }
+        Ok(expansion.resolved)
+    }
+}
+
+/// The state of a resolution, shared by the sync and async variants.
+///
+/// Pending addresses are expanded depth-first, so that the resolved addresses keep the
+/// order of the DNS answers.
+struct Expansion {
+    pending: VecDeque<Pending>,
+    resolved: Vec<Multiaddr>,
+    lookups: usize,
+}
+
+struct Pending {
+    addr: Multiaddr,
+    /// The `/dnsaddr` names this address was resolved from, see [`normalize_dnsaddr`].
+    dnsaddrs: Vec<String>,
+}
+
+enum Family {
+    Any,
+    V4,
+    V6,
+}
+
+enum Query {
+    Ip {
+        name: String,
+        family: Family,
+        pending: Pending,
+        index: usize,
+    },
+    Txt {
+        name: String,
+        dnsaddr: String,
+        pending: Pending,
+        index: usize,
+    },
+}
+
+enum Answer {
+    Ip(Vec<IpAddr>),
+    Txt(Vec<String>),
+}
+
+impl Expansion {
+    fn new(addr: &MultiaddrSlice) -> Self {
+        Expansion {
+            pending: VecDeque::from([Pending {
+                addr: addr.to_owned(),
+                dnsaddrs: Vec::new(),
+            }]),
+            resolved: Vec::new(),
+            lookups: 0,
+        }
+    }
+
+    /// Returns the next lookup to perform, moving fully resolved addresses to `resolved`.
+    fn next_query(&mut self) -> Option<Query> {
+        while let Some(pending) = self.pending.pop_front() {
+            if self.resolved.len() >= MAX_RESOLVED_ADDRS {
+                return None;
+            }
+            let query = pending
+                .addr
+                .iter()
+                .enumerate()
+                .find_map(|(index, p)| match p {
+                    Protocol::Dns(name) => Some((index, name.into_owned(), Some(Family::Any))),
+                    Protocol::Dns4(name) => Some((index, name.into_owned(), Some(Family::V4))),
+                    Protocol::Dns6(name) => Some((index, name.into_owned(), Some(Family::V6))),
+                    Protocol::Dnsaddr(name) => Some((index, name.into_owned(), None)),
+                    _ => None,
+                });
+            let Some((index, name, family)) = query else {
+                self.resolved.push(pending.addr);
+                continue;
+            };
+            if self.lookups >= MAX_LOOKUPS {
+                continue;
+            }
+            match family {
+                Some(family) => {
+                    self.lookups += 1;
+                    return Some(Query::Ip {
+                        name,
+                        family,
+                        pending,
+                        index,
+                    });
+                }
+                None => {
+                    let dnsaddr = normalize_dnsaddr(&name);
+                    if pending.dnsaddrs.len() >= MAX_DNSADDR_DEPTH
+                        || pending.dnsaddrs.contains(&dnsaddr)
+                    {
+                        continue;
+                    }
+                    self.lookups += 1;
+                    return Some(Query::Txt {
+                        name: format!("_dnsaddr.{name}"),
+                        dnsaddr,
+                        pending,
+                        index,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn answer(&mut self, query: Query, answer: io::Result<Answer>) -> Result<(), ResolveError> {
+        let mut expanded = Vec::new();
+        match (query, answer) {
+            (Query::Ip { name, pending, .. } | Query::Txt { name, pending, .. }, Err(error)) => {
+                // Only a lookup of the original address fails the resolution, one from a
+                // `/dnsaddr` record just drops that record.
+                if pending.dnsaddrs.is_empty() {
+                    return Err(ResolveError { name, error });
+                }
+            }
+            (
+                Query::Ip {
+                    family,
+                    pending,
+                    index,
+                    ..
+                },
+                Ok(Answer::Ip(ips)),
+            ) => {
+                for ip in ips {
+                    let p = match (&family, ip) {
+                        (Family::Any | Family::V4, IpAddr::V4(ip)) => Protocol::Ip4(ip),
+                        (Family::Any | Family::V6, IpAddr::V6(ip)) => Protocol::Ip6(ip),
+                        _ => continue,
+                    };
+                    let addr = pending
+                        .addr
+                        .replace(index, |_| Some(p))
+                        .expect("index exists");
+                    expanded.push(Pending {
+                        addr,
+                        dnsaddrs: pending.dnsaddrs.clone(),
+                    });
+                }
+            }
+            (
+                Query::Txt {
+                    dnsaddr,
+                    pending,
+                    index,
+                    ..
+                },
+                Ok(Answer::Txt(records)),
+            ) => {
+                let prefix: Multiaddr = pending.addr.iter().take(index).collect();
+                let suffix: Multiaddr = pending.addr.iter().skip(index + 1).collect();
+                let mut dnsaddrs = pending.dnsaddrs;
+                dnsaddrs.push(dnsaddr);
+                for record in records {
+                    let Ok(record) = dnsaddr::parse_record(&record) else {
+                        continue;
+                    };
+                    if record.strip_suffix(&suffix).is_none() {
+                        continue;
+                    }
+                    expanded.push(Pending {
+                        addr: prefix.encapsulate(&record),
+                        dnsaddrs: dnsaddrs.clone(),
+                    });
+                }
+            }
+            (Query::Ip { .. }, Ok(Answer::Txt(_))) | (Query::Txt { .. }, Ok(Answer::Ip(_))) => {
+                unreachable!("answers match their query")
+            }
+        }
+        for pending in expanded.into_iter().rev() {
+            self.pending.push_front(pending);
+        }
+        Ok(())
+    }
+}
+
+/// Returns the name of a `/dnsaddr` in the form in which cycles are detected, i.e.
+/// lowercased and without a trailing dot.
+fn normalize_dnsaddr(name: &str) -> String {
+    name.trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// Error while resolving a multiaddress.
+#[derive(Debug)]
+pub struct ResolveError {
+    name: String,
+    error: io::Error,
+}
+
+impl ResolveError {
+    /// Returns the name whose lookup failed.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the error of the failed lookup.
+    pub fn io_error(&self) -> &io::Error {
+        &self.error
+    }
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to resolve `{}`: {}", self.name, self.error)
+    }
+}
+
+impl error::Error for ResolveError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// An in-memory [`Resolver`] and [`AsyncResolver`], e.g. for tests.
+///
+/// Names are matched case-insensitively and without a trailing dot. Looking up a name
+/// without any records fails with [`io::ErrorKind::NotFound`].
+#[derive(Debug, Clone, Default)]
+pub struct MockResolver {
+    ips: HashMap<String, Vec<IpAddr>>,
+    txts: HashMap<String, Vec<String>>,
+}
+
+impl MockResolver {
+    /// Creates a resolver without any records.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an IP address record for `name`.
+    pub fn add_ip(&mut self, name: &str, ip: IpAddr) {
+        self.ips.entry(normalize(name)).or_default().push(ip);
+    }
+
+    /// Adds a TXT record for `name`.
+    pub fn add_txt(&mut self, name: &str, txt: impl Into<String>) {
+        self.txts
+            .entry(normalize(name))
+            .or_default()
+            .push(txt.into());
+    }
+
+    fn lookup<T: Clone>(
+        &self,
+        records: &HashMap<String, Vec<T>>,
+        name: &str,
+    ) -> io::Result<Vec<T>> {
+        let name = normalize(name);
+        if !self.ips.contains_key(&name) && !self.txts.contains_key(&name) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no records for `{name}`"),
+            ));
+        }
+        Ok(records.get(&name).cloned().unwrap_or_default())
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.strip_suffix('.').unwrap_or(name).to_ascii_lowercase()
+}
+
+impl Resolver for MockResolver {
+    fn lookup_ip(&self, name: &str) -> io::Result<Vec<IpAddr>> {
+        self.lookup(&self.ips, name)
+    }
+
+    fn lookup_txt(&self, name: &str) -> io::Result<Vec<String>> {
+        self.lookup(&self.txts, name)
+    }
+}
+
+impl AsyncResolver for MockResolver {
+    fn lookup_ip<'a>(&'a self, name: &'a str) -> BoxFuture<'a, io::Result<Vec<IpAddr>>> {
+        Box::pin(std::future::ready(Resolver::lookup_ip(self, name)))
+    }
+
+    fn lookup_txt<'a>(&'a self, name: &'a str) -> BoxFuture<'a, io::Result<Vec<String>>> {
+        Box::pin(std::future::ready(Resolver::lookup_txt(self, name)))
+    }
+}
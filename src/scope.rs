@@ -0,0 +1,199 @@
+use crate::{MultiaddrSlice, Protocol};
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// The reachability scope of a multiaddress, see [`MultiaddrSlice::scope`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Scope {
+    /// Reachable within the same host without a network, i.e. `/memory` and `/unix`.
+    Host,
+    /// The unspecified address, `0.0.0.0` or `::`, as used for listening.
+    Unspecified,
+    /// Loopback addresses (`127.0.0.0/8`, `::1`) and `localhost` names.
+    Loopback,
+    /// Link-local addresses (`169.254.0.0/16`, `fe80::/10`) and mDNS `.local` names.
+    LinkLocal,
+    /// RFC 1918 private networks and the `.home.arpa` and `.internal` names.
+    Private,
+    /// The carrier-grade NAT range `100.64.0.0/10` (RFC 6598).
+    SharedAddressSpace,
+    /// IPv6 unique local addresses, `fc00::/7` (RFC 4193).
+    UniqueLocal,
+    /// Address ranges reserved for documentation.
+    Documentation,
+    /// Multicast addresses.
+    Multicast,
+    /// Other special-purpose ranges that are not globally reachable, e.g. benchmarking,
+    /// broadcast or future use.
+    Reserved,
+    /// Globally reachable addresses and DNS names.
+    Global,
+    /// The multiaddress has no host the scope can be derived from, e.g. `/onion3` or `/tcp/80`.
+    Unknown,
+}
+
+/// Special-purpose IPv4 ranges, from the IANA IPv4 Special-Purpose Address Registry.
+///
+/// More specific entries come first, the first matching entry wins. Addresses not
+/// covered by any entry are [`Scope::Global`].
+const IPV4_SPECIAL: &[([u8; 4], u8, Scope)] = &[
+    ([0, 0, 0, 0], 32, Scope::Unspecified),
+    ([0, 0, 0, 0], 8, Scope::Reserved),
+    ([10, 0, 0, 0], 8, Scope::Private),
+    ([100, 64, 0, 0], 10, Scope::SharedAddressSpace),
+    ([127, 0, 0, 0], 8, Scope::Loopback),
+    ([169, 254, 0, 0], 16, Scope::LinkLocal),
+    ([172, 16, 0, 0], 12, Scope::Private),
+    // Port Control Protocol and TURN anycast, globally reachable.
+    ([192, 0, 0, 9], 32, Scope::Global),
+    ([192, 0, 0, 10], 32, Scope::Global),
+    ([192, 0, 0, 0], 24, Scope::Reserved),
+    ([192, 0, 2, 0], 24, Scope::Documentation),
+    // Deprecated 6to4 relay anycast.
+    ([192, 88, 99, 0], 24, Scope::Reserved),
+    ([192, 168, 0, 0], 16, Scope::Private),
+    // Benchmarking.
+    ([198, 18, 0, 0], 15, Scope::Reserved),
+    ([198, 51, 100, 0], 24, Scope::Documentation),
+    ([203, 0, 113, 0], 24, Scope::Documentation),
+    ([224, 0, 0, 0], 4, Scope::Multicast),
+    ([255, 255, 255, 255], 32, Scope::Reserved),
+    ([240, 0, 0, 0], 4, Scope::Reserved),
+];
+
+/// Special-purpose IPv6 ranges, from the IANA IPv6 Special-Purpose Address Registry.
+///
+/// More specific entries come first, the first matching entry wins. Addresses not
+/// covered by any entry are [`Scope::Global`]. IPv4-mapped addresses are classified
+/// as their IPv4 counterpart before this table is consulted.
+const IPV6_SPECIAL: &[([u16; 8], u8, Scope)] = &[
+    ([0, 0, 0, 0, 0, 0, 0, 0], 128, Scope::Unspecified),
+    ([0, 0, 0, 0, 0, 0, 0, 1], 128, Scope::Loopback),
+    // Local-use IPv4/IPv6 translation.
+    ([0x64, 0xff9b, 1, 0, 0, 0, 0, 0], 48, Scope::Reserved),
+    // Discard-only.
+    ([0x100, 0, 0, 0, 0, 0, 0, 0], 64, Scope::Reserved),
+    // Globally reachable IETF protocol assignments: PCP and TURN anycast, AMT, AS112
+    // and ORCHIDv2.
+    ([0x2001, 1, 0, 0, 0, 0, 0, 1], 128, Scope::Global),
+    ([0x2001, 1, 0, 0, 0, 0, 0, 2], 128, Scope::Global),
+    ([0x2001, 3, 0, 0, 0, 0, 0, 0], 32, Scope::Global),
+    ([0x2001, 4, 0x112, 0, 0, 0, 0, 0], 48, Scope::Global),
+    ([0x2001, 0x20, 0, 0, 0, 0, 0, 0], 28, Scope::Global),
+    // Remaining IETF protocol assignments, including Teredo and benchmarking.
+    ([0x2001, 0, 0, 0, 0, 0, 0, 0], 23, Scope::Reserved),
+    ([0x2001, 0xdb8, 0, 0, 0, 0, 0, 0], 32, Scope::Documentation),
+    ([0x3fff, 0, 0, 0, 0, 0, 0, 0], 20, Scope::Documentation),
+    // Segment Routing (SRv6) SIDs.
+    ([0x5f00, 0, 0, 0, 0, 0, 0, 0], 16, Scope::Reserved),
+    ([0xfc00, 0, 0, 0, 0, 0, 0, 0], 7, Scope::UniqueLocal),
+    ([0xfe80, 0, 0, 0, 0, 0, 0, 0], 10, Scope::LinkLocal),
+    // Deprecated site-local addresses.
+    ([0xfec0, 0, 0, 0, 0, 0, 0, 0], 10, Scope::Reserved),
+    ([0xff00, 0, 0, 0, 0, 0, 0, 0], 8, Scope::Multicast),
+];
+
+impl Scope {
+    /// Returns the scope of an IP address.
+    pub fn of_ip(ip: IpAddr) -> Scope {
+        match ip {
+            IpAddr::V4(ip) => Self::of_ipv4(ip),
+            IpAddr::V6(ip) => Self::of_ipv6(ip),
+        }
+    }
+
+    fn of_ipv4(ip: Ipv4Addr) -> Scope {
+        let ip = u32::from(ip);
+        IPV4_SPECIAL
+            .iter()
+            .find(|(net, prefix, _)| {
+                prefix_matches(ip.into(), u32::from_be_bytes(*net).into(), 32, *prefix)
+            })
+            .map_or(Scope::Global, |(_, _, scope)| *scope)
+    }
+
+    fn of_ipv6(ip: Ipv6Addr) -> Scope {
+        if let Some(ip) = ip.to_ipv4_mapped() {
+            return Self::of_ipv4(ip);
+        }
+        let ip = u128::from(ip);
+        IPV6_SPECIAL
+            .iter()
+            .find(|(net, prefix, _)| prefix_matches(ip, Ipv6Addr::from(*net).into(), 128, *prefix))
+            .map_or(Scope::Global, |(_, _, scope)| *scope)
+    }
+
+    /// Returns the scope of a DNS name.
+    ///
+    /// `localhost`, `.local`, `.home.arpa` and `.internal` names are special-use names that
+    /// never resolve through the public DNS. All other names are considered [`Scope::Global`].
+    pub fn of_domain(name: &str) -> Scope {
+        let name = name.strip_suffix('.').unwrap_or(name).to_ascii_lowercase();
+        let has_suffix = |suffix: &str| {
+            name == suffix
+                || name
+                    .strip_suffix(suffix)
+                    .is_some_and(|prefix| prefix.ends_with('.'))
+        };
+        if has_suffix("localhost") {
+            Scope::Loopback
+        } else if has_suffix("local") {
+            Scope::LinkLocal
+        } else if has_suffix("home.arpa") || has_suffix("internal") {
+            Scope::Private
+        } else {
+            Scope::Global
+        }
+    }
+}
+
+fn prefix_matches(ip: u128, net: u128, bits: u32, prefix: u8) -> bool {
+    let shift = bits - u32::from(prefix);
+    ip.checked_shr(shift).unwrap_or(0) == net.checked_shr(shift).unwrap_or(0)
+}
+
+impl MultiaddrSlice {
+    /// Returns the reachability scope of this multiaddress.
+    ///
+    /// The scope is derived from the first host component: `/ip4`, `/ip6` (skipping any
+    /// `/ip6zone`), one of the `/dns` variants, `/memory` or `/unix`. IPv4-mapped IPv6
+    /// addresses are classified as their IPv4 counterpart.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use multiaddr::{Multiaddr, Scope};
+    ///
+    /// let addr: Multiaddr = "/ip4/100.64.1.2/tcp/80".parse().unwrap();
+    /// assert_eq!(addr.scope(), Scope::SharedAddressSpace);
+    ///
+    /// let addr: Multiaddr = "/ip6/::ffff:192.168.1.1/tcp/80".parse().unwrap();
+    /// assert_eq!(addr.scope(), Scope::Private);
+    ///
+    /// let addr: Multiaddr = "/dns/node.local/tcp/80".parse().unwrap();
+    /// assert_eq!(addr.scope(), Scope::LinkLocal);
+    /// ```
+    ///
+    pub fn scope(&self) -> Scope {
+        for p in self.iter() {
+            match p {
+                Protocol::Ip4(ip) => return Scope::of_ip(ip.into()),
+                Protocol::Ip6(ip) => return Scope::of_ip(ip.into()),
+                Protocol::Ip6zone(_) => continue,
+                Protocol::Dns(name)
+                | Protocol::Dns4(name)
+                | Protocol::Dns6(name)
+                | Protocol::Dnsaddr(name) => return Scope::of_domain(&name),
+                Protocol::Memory(_) | Protocol::Unix(_) => return Scope::Host,
+                _ => return Scope::Unknown,
+            }
+        }
+        Scope::Unknown
+    }
+
+    /// Checks whether this multiaddress is reachable from the public internet, i.e. whether
+    /// its [`scope`](MultiaddrSlice::scope) is [`Scope::Global`].
+    pub fn is_globally_routable(&self) -> bool {
+        self.scope() == Scope::Global
+    }
+}
@@ -0,0 +1,379 @@
+use crate::{Error, Iter, Multiaddr, ProtoStackIter, Protocol, Result};
+use alloc::{borrow::ToOwned, vec::Vec};
+use bytes::Bytes;
+#[cfg(feature = "serde")]
+use core::result::Result as StdResult;
+use core::{borrow::Borrow, convert::TryFrom, fmt, ops::Deref};
+#[cfg(feature = "serde")]
+use serde::{
+    de::{self, Error as DeserializerError},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+/// A borrowed, validated multiaddress.
+///
+/// `MultiaddrSlice` relates to [`Multiaddr`] like `Path` relates to `PathBuf`: it is an
+/// unsized view into bytes that are known to form a valid multiaddress, and is only ever
+/// used behind a reference. [`Multiaddr`] dereferences to it.
+///
+/// # Example
+///
+/// ```
+/// use multiaddr::{Multiaddr, MultiaddrSlice};
+///
+/// let bytes = [4, 127, 0, 0, 1, 6, 0, 80];
+/// let slice = MultiaddrSlice::from_bytes(&bytes).unwrap();
+/// assert_eq!(slice.to_string(), "/ip4/127.0.0.1/tcp/80");
+///
+/// let address: Multiaddr = slice.to_owned();
+/// assert!(address.starts_with(slice));
+/// ```
+///
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct MultiaddrSlice([u8]);
+
+impl MultiaddrSlice {
+    /// Validates the given bytes and returns them as a multiaddress, without copying.
+    pub fn from_bytes(bytes: &[u8]) -> Result<&MultiaddrSlice> {
+        validate(bytes)?;
+        Ok(Self::from_bytes_unchecked(bytes))
+    }
+
+    /// Wraps bytes that are already known to form a valid multiaddress.
+    pub(crate) fn from_bytes_unchecked(bytes: &[u8]) -> &MultiaddrSlice {
+        // SAFETY: `MultiaddrSlice` is a `repr(transparent)` wrapper around `[u8]`.
+        unsafe { &*(bytes as *const [u8] as *const MultiaddrSlice) }
+    }
+
+    /// Return the length in bytes of this multiaddress.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if the length of this multiaddress is 0.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Return a copy of this multiaddress' byte representation.
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+
+    /// Returns the components of this multiaddress.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::net::Ipv4Addr;
+    /// use multiaddr::{Multiaddr, Protocol};
+    ///
+    /// let address: Multiaddr = "/ip4/127.0.0.1/udt/sctp/5678".parse().unwrap();
+    ///
+    /// let components = address.iter().collect::<Vec<_>>();
+    /// assert_eq!(components[0], Protocol::Ip4(Ipv4Addr::new(127, 0, 0, 1)));
+    /// assert_eq!(components[1], Protocol::Udt);
+    /// assert_eq!(components[2], Protocol::Sctp(5678));
+    /// ```
+    ///
+    pub fn iter(&self) -> Iter<'_> {
+        Iter(&self.0)
+    }
+
+    /// Checks whether the given multiaddress is a suffix of this multiaddress.
+    pub fn ends_with(&self, other: &MultiaddrSlice) -> bool {
+        self.0.ends_with(&other.0)
+    }
+
+    /// Checks whether the given multiaddress is a prefix of this multiaddress.
+    pub fn starts_with(&self, other: &MultiaddrSlice) -> bool {
+        self.0.starts_with(&other.0)
+    }
+
+    /// Returns &str identifiers for the protocol names themselves.
+    /// This omits specific info like addresses, ports, peer IDs, and the like.
+    /// Example: `"/ip4/127.0.0.1/tcp/5001"` would return `["ip4", "tcp"]`
+    pub fn protocol_stack(&self) -> ProtoStackIter<'_> {
+        ProtoStackIter { parts: self.iter() }
+    }
+
+    /// Returns a new multiaddress with `other` appended to this one.
+    pub fn encapsulate(&self, other: &MultiaddrSlice) -> Multiaddr {
+        let mut bytes = Vec::with_capacity(self.len() + other.len());
+        bytes.extend_from_slice(&self.0);
+        bytes.extend_from_slice(&other.0);
+        Multiaddr {
+            bytes: bytes.into(),
+        }
+    }
+
+    /// Removes the last occurrence of `other` and everything after it.
+    ///
+    /// Returns this multiaddress unchanged if `other` is empty or does not occur in it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use multiaddr::Multiaddr;
+    ///
+    /// let addr: Multiaddr = "/ip4/1.2.3.4/tcp/80/ws/tcp/80/ws".parse().unwrap();
+    /// let ws: Multiaddr = "/tcp/80/ws".parse().unwrap();
+    /// assert_eq!(addr.decapsulate(&ws).to_string(), "/ip4/1.2.3.4/tcp/80/ws");
+    /// ```
+    ///
+    pub fn decapsulate(&self, other: &MultiaddrSlice) -> &MultiaddrSlice {
+        if other.is_empty() {
+            return self;
+        }
+        self.offsets()
+            .filter(|(offset, _)| self.0[*offset..].starts_with(&other.0))
+            .last()
+            .map_or(self, |(offset, _)| self.slice(..offset))
+    }
+
+    /// Removes the last component with the given [code](Protocol::code) and everything
+    /// after it.
+    ///
+    /// The code is either a [`ProtocolKind`](crate::ProtocolKind) or the raw code of a custom protocol.
+    ///
+    /// Returns this multiaddress unchanged if no component has the code.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use multiaddr::{Multiaddr, ProtocolKind};
+    ///
+    /// let addr: Multiaddr = "/ip4/1.2.3.4/tcp/80/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit"
+    ///     .parse()
+    ///     .unwrap();
+    /// let relay = addr.decapsulate_code(ProtocolKind::P2pCircuit);
+    /// assert_eq!(
+    ///     relay.to_string(),
+    ///     "/ip4/1.2.3.4/tcp/80/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC"
+    /// );
+    /// ```
+    ///
+    pub fn decapsulate_code(&self, code: impl Into<u32>) -> &MultiaddrSlice {
+        self.split_last(code).map_or(self, |(before, _, _)| before)
+    }
+
+    /// Splits this multiaddress at the first component with the given
+    /// [code](Protocol::code), returning the components before it, the component itself
+    /// and the components after it.
+    pub fn split_first(
+        &self,
+        code: impl Into<u32>,
+    ) -> Option<(&MultiaddrSlice, Protocol<'_>, &MultiaddrSlice)> {
+        let code = code.into();
+        let (offset, p) = self.offsets().find(|(_, p)| p.code() == code)?;
+        Some(self.split_at(offset, p))
+    }
+
+    /// Splits this multiaddress at the last component with the given
+    /// [code](Protocol::code), returning the components before it, the component itself
+    /// and the components after it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use multiaddr::{Multiaddr, ProtocolKind};
+    ///
+    /// let addr: Multiaddr = "/ip4/1.2.3.4/tcp/80/p2p-circuit/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC"
+    ///     .parse()
+    ///     .unwrap();
+    /// let (relay, _, destination) = addr.split_last(ProtocolKind::P2pCircuit).unwrap();
+    /// assert_eq!(relay.to_string(), "/ip4/1.2.3.4/tcp/80");
+    /// assert_eq!(
+    ///     destination.to_string(),
+    ///     "/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC"
+    /// );
+    /// ```
+    ///
+    pub fn split_last(
+        &self,
+        code: impl Into<u32>,
+    ) -> Option<(&MultiaddrSlice, Protocol<'_>, &MultiaddrSlice)> {
+        let code = code.into();
+        let (offset, p) = self.offsets().filter(|(_, p)| p.code() == code).last()?;
+        Some(self.split_at(offset, p))
+    }
+
+    /// Returns the remainder of this multiaddress after `prefix`, if it starts with `prefix`.
+    pub fn strip_prefix(&self, prefix: &MultiaddrSlice) -> Option<&MultiaddrSlice> {
+        self.0
+            .strip_prefix(&prefix.0)
+            .map(MultiaddrSlice::from_bytes_unchecked)
+    }
+
+    /// Returns the remainder of this multiaddress before `suffix`, if it ends with `suffix`.
+    ///
+    /// Unlike [`ends_with`](MultiaddrSlice::ends_with), this only matches suffixes that
+    /// start at a component boundary.
+    pub fn strip_suffix(&self, suffix: &MultiaddrSlice) -> Option<&MultiaddrSlice> {
+        let offset = self.len().checked_sub(suffix.len())?;
+        if self.0[offset..] != suffix.0 {
+            return None;
+        }
+        if offset != self.len() && !self.offsets().any(|(o, _)| o == offset) {
+            return None;
+        }
+        Some(self.slice(..offset))
+    }
+
+    /// Returns the components together with their byte offset.
+    fn offsets(&self) -> impl Iterator<Item = (usize, Protocol<'_>)> {
+        let mut iter = self.iter();
+        core::iter::from_fn(move || {
+            let offset = self.len() - iter.0.len();
+            iter.next().map(|p| (offset, p))
+        })
+    }
+
+    /// Splits this multiaddress around the component `p` at `offset`.
+    fn split_at<'a>(
+        &'a self,
+        offset: usize,
+        p: Protocol<'a>,
+    ) -> (&'a MultiaddrSlice, Protocol<'a>, &'a MultiaddrSlice) {
+        let end = self.len()
+            - Protocol::from_bytes(&self.0[offset..])
+                .expect("valid")
+                .1
+                .len();
+        (self.slice(..offset), p, self.slice(end..))
+    }
+
+    /// Returns the given byte range, which must lie on component boundaries.
+    fn slice<R: core::slice::SliceIndex<[u8], Output = [u8]>>(&self, range: R) -> &MultiaddrSlice {
+        MultiaddrSlice::from_bytes_unchecked(&self.0[range])
+    }
+}
+
+/// Checks that `bytes` form a valid multiaddress by reading its protocols.
+pub(crate) fn validate(bytes: &[u8]) -> Result<()> {
+    let mut rest = bytes;
+    let mut index = 0;
+    while !rest.is_empty() {
+        rest = match Protocol::from_bytes(rest) {
+            Ok((_, next)) => next,
+            Err(e) => return Err(Error::in_bytes(index, bytes.len() - rest.len(), rest, e)),
+        };
+        index += 1;
+    }
+    Ok(())
+}
+
+impl fmt::Debug for MultiaddrSlice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::Display for MultiaddrSlice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for s in self.iter() {
+            s.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl AsRef<[u8]> for MultiaddrSlice {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<'a> IntoIterator for &'a MultiaddrSlice {
+    type Item = Protocol<'a>;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        Iter(&self.0)
+    }
+}
+
+impl ToOwned for MultiaddrSlice {
+    type Owned = Multiaddr;
+
+    fn to_owned(&self) -> Multiaddr {
+        Multiaddr {
+            bytes: Bytes::copy_from_slice(&self.0),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for &'a MultiaddrSlice {
+    type Error = Error;
+
+    fn try_from(bytes: &'a [u8]) -> Result<Self> {
+        MultiaddrSlice::from_bytes(bytes)
+    }
+}
+
+impl Deref for Multiaddr {
+    type Target = MultiaddrSlice;
+
+    fn deref(&self) -> &MultiaddrSlice {
+        MultiaddrSlice::from_bytes_unchecked(&self.bytes)
+    }
+}
+
+impl AsRef<MultiaddrSlice> for Multiaddr {
+    fn as_ref(&self) -> &MultiaddrSlice {
+        self
+    }
+}
+
+impl Borrow<MultiaddrSlice> for Multiaddr {
+    fn borrow(&self) -> &MultiaddrSlice {
+        self
+    }
+}
+
+impl From<&MultiaddrSlice> for Multiaddr {
+    fn from(slice: &MultiaddrSlice) -> Multiaddr {
+        slice.to_owned()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for MultiaddrSlice {
+    fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_bytes(&self.0)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de: 'a, 'a> Deserialize<'de> for &'a MultiaddrSlice {
+    /// Deserializes a multiaddress by borrowing its binary representation from the input.
+    ///
+    /// Only formats that are not human readable and support borrowed bytes can be used.
+    fn deserialize<D>(deserializer: D) -> StdResult<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct Visitor;
+
+        impl<'de> de::Visitor<'de> for Visitor {
+            type Value = &'de MultiaddrSlice;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("borrowed multiaddress bytes")
+            }
+            fn visit_borrowed_bytes<E: de::Error>(self, v: &'de [u8]) -> StdResult<Self::Value, E> {
+                MultiaddrSlice::from_bytes(v).map_err(DeserializerError::custom)
+            }
+        }
+
+        deserializer.deserialize_bytes(Visitor)
+    }
+}
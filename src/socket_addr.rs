@@ -0,0 +1,169 @@
+use crate::{Multiaddr, Protocol};
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+};
+use core::{
+    convert::TryFrom,
+    error, fmt,
+    net::{IpAddr, SocketAddr, SocketAddrV4, SocketAddrV6},
+};
+
+/// The transport protocol carried on top of an IP address in a socket address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transport {
+    /// `/tcp`
+    Tcp,
+    /// `/udp`
+    Udp,
+}
+
+impl Multiaddr {
+    /// Creates a multiaddress of the form `/ip4/<ip>/<transport>/<port>` or
+    /// `/ip6/<ip>/<transport>/<port>` from a socket address.
+    ///
+    /// A non-zero scope id of an IPv6 socket address is encoded as a preceding `/ip6zone`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use multiaddr::{Multiaddr, Transport};
+    ///
+    /// let addr = Multiaddr::from_socket_addr("127.0.0.1:8080".parse().unwrap(), Transport::Tcp);
+    /// assert_eq!(addr, "/ip4/127.0.0.1/tcp/8080".parse().unwrap());
+    /// ```
+    ///
+    pub fn from_socket_addr(addr: SocketAddr, transport: Transport) -> Multiaddr {
+        let mut address = Multiaddr::with_capacity(24);
+        match addr {
+            SocketAddr::V4(addr) => address.push(Protocol::Ip4(*addr.ip())),
+            SocketAddr::V6(addr) => {
+                if addr.scope_id() != 0 {
+                    address.push(Protocol::Ip6zone(addr.scope_id().to_string().into()));
+                }
+                address.push(Protocol::Ip6(*addr.ip()));
+            }
+        }
+        match transport {
+            Transport::Tcp => address.with(Protocol::Tcp(addr.port())),
+            Transport::Udp => address.with(Protocol::Udp(addr.port())),
+        }
+    }
+
+    /// Splits this multiaddress into the socket address described by its leading
+    /// `/ip4` or `/ip6` and `/tcp` or `/udp` components, the transport, and the rest
+    /// of the address.
+    ///
+    /// An `/ip6zone` directly before or after the `/ip6` component is mapped to the scope
+    /// id of the resulting [`SocketAddrV6`]. Only numeric zones can be mapped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use multiaddr::{Multiaddr, Transport};
+    ///
+    /// let addr: Multiaddr = "/ip4/127.0.0.1/udp/443/quic-v1".parse().unwrap();
+    /// let (socket, transport, rest) = addr.to_socket_addr().unwrap();
+    /// assert_eq!(socket, "127.0.0.1:443".parse().unwrap());
+    /// assert_eq!(transport, Transport::Udp);
+    /// assert_eq!(rest, "/quic-v1".parse().unwrap());
+    /// ```
+    ///
+    pub fn to_socket_addr(
+        &self,
+    ) -> core::result::Result<(SocketAddr, Transport, Multiaddr), SocketAddrErr> {
+        let mut iter = self.iter().enumerate().peekable();
+
+        let (ip, scope_id) = match iter.next().ok_or(SocketAddrErr::Incomplete)? {
+            (_, Protocol::Ip4(ip)) => (IpAddr::V4(ip), None),
+            (_, Protocol::Ip6(ip)) => {
+                match iter.next_if(|(_, p)| matches!(p, Protocol::Ip6zone(_))) {
+                    Some((_, Protocol::Ip6zone(zone))) => {
+                        (IpAddr::V6(ip), Some(parse_zone(&zone)?))
+                    }
+                    _ => (IpAddr::V6(ip), None),
+                }
+            }
+            (_, Protocol::Ip6zone(zone)) => match iter.next().ok_or(SocketAddrErr::Incomplete)? {
+                (_, Protocol::Ip6(ip)) => (IpAddr::V6(ip), Some(parse_zone(&zone)?)),
+                (index, p) => return Err(SocketAddrErr::unexpected(index, &p)),
+            },
+            (index, p) => return Err(SocketAddrErr::unexpected(index, &p)),
+        };
+
+        let (port, transport) = match iter.next().ok_or(SocketAddrErr::Incomplete)? {
+            (_, Protocol::Tcp(port)) => (port, Transport::Tcp),
+            (_, Protocol::Udp(port)) => (port, Transport::Udp),
+            (index, p) => return Err(SocketAddrErr::unexpected(index, &p)),
+        };
+
+        let socket = match ip {
+            IpAddr::V4(ip) => SocketAddr::V4(SocketAddrV4::new(ip, port)),
+            IpAddr::V6(ip) => SocketAddr::V6(SocketAddrV6::new(ip, port, 0, scope_id.unwrap_or(0))),
+        };
+
+        Ok((socket, transport, iter.map(|(_, p)| p).collect()))
+    }
+}
+
+impl TryFrom<&Multiaddr> for SocketAddr {
+    type Error = SocketAddrErr;
+
+    /// Converts a multiaddress consisting only of an IP address and a `/tcp` or `/udp` port.
+    ///
+    /// Use [`Multiaddr::to_socket_addr`] to convert addresses with additional components.
+    fn try_from(addr: &Multiaddr) -> core::result::Result<Self, Self::Error> {
+        let (socket, _, rest) = addr.to_socket_addr()?;
+        match rest.iter().next() {
+            None => Ok(socket),
+            Some(p) => Err(SocketAddrErr::unexpected(
+                addr.iter().count() - rest.iter().count(),
+                &p,
+            )),
+        }
+    }
+}
+
+fn parse_zone(zone: &str) -> core::result::Result<u32, SocketAddrErr> {
+    zone.parse()
+        .map_err(|_| SocketAddrErr::InvalidZone(zone.to_owned()))
+}
+
+/// Error while turning a multiaddress into a socket address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SocketAddrErr {
+    /// The multiaddress ends before an IP address and a port were found.
+    Incomplete,
+    /// The component at `index` cannot be part of a socket address at its position.
+    UnexpectedProtocol {
+        /// Index of the offending component.
+        index: usize,
+        /// Tag of the offending component, see [`Protocol::tag`].
+        tag: &'static str,
+    },
+    /// The `/ip6zone` value is not a numeric scope id.
+    InvalidZone(String),
+}
+
+impl SocketAddrErr {
+    fn unexpected(index: usize, p: &Protocol<'_>) -> Self {
+        SocketAddrErr::UnexpectedProtocol {
+            index,
+            tag: p.tag(),
+        }
+    }
+}
+
+impl fmt::Display for SocketAddrErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SocketAddrErr::Incomplete => f.write_str("multiaddr does not contain an ip and a port"),
+            SocketAddrErr::UnexpectedProtocol { index, tag } => {
+                write!(f, "unexpected protocol `{tag}` at component {index}")
+            }
+            SocketAddrErr::InvalidZone(zone) => write!(f, "ip6zone `{zone}` is not a scope id"),
+        }
+    }
+}
+
+impl error::Error for SocketAddrErr {}
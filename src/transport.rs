@@ -0,0 +1,108 @@
+use crate::{MultiaddrSlice, Protocol};
+
+/// The transport a multiaddress is reached over, see [`MultiaddrSlice::transport`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TransportKind {
+    Tcp,
+    Udp,
+    /// Draft-29 QUIC.
+    Quic,
+    QuicV1,
+    /// `/ws` or `/wss`, with or without a path. `secure` is set for `/tls/ws` and `/wss`.
+    WebSocket {
+        secure: bool,
+    },
+    WebTransport,
+    /// Browser-to-browser WebRTC, established through a relay.
+    WebRTC,
+    WebRTCDirect,
+    Memory,
+    Unix,
+    Onion,
+    /// A relayed connection through `/p2p-circuit`.
+    Circuit,
+}
+
+impl MultiaddrSlice {
+    /// Returns the outermost transport of this multiaddress, if any.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use multiaddr::{Multiaddr, TransportKind};
+    ///
+    /// let addr: Multiaddr = "/dns/example.com/tcp/443/tls/ws".parse().unwrap();
+    /// assert_eq!(addr.transport(), Some(TransportKind::WebSocket { secure: true }));
+    ///
+    /// let addr: Multiaddr = "/ip4/1.2.3.4/udp/443/quic-v1/webtransport".parse().unwrap();
+    /// assert_eq!(addr.transport(), Some(TransportKind::WebTransport));
+    /// ```
+    ///
+    pub fn transport(&self) -> Option<TransportKind> {
+        let mut transport = None;
+        let mut tls = false;
+        for p in self.iter() {
+            transport = match p {
+                Protocol::Tcp(_) => Some(TransportKind::Tcp),
+                Protocol::Udp(_) => Some(TransportKind::Udp),
+                Protocol::Quic => Some(TransportKind::Quic),
+                Protocol::QuicV1 => Some(TransportKind::QuicV1),
+                Protocol::Ws(_) => Some(TransportKind::WebSocket { secure: tls }),
+                Protocol::Wss(_) => Some(TransportKind::WebSocket { secure: true }),
+                Protocol::WebTransport => Some(TransportKind::WebTransport),
+                Protocol::WebRTC => Some(TransportKind::WebRTC),
+                Protocol::WebRTCDirect => Some(TransportKind::WebRTCDirect),
+                Protocol::Memory(_) => Some(TransportKind::Memory),
+                Protocol::Unix(_) => Some(TransportKind::Unix),
+                Protocol::Onion(_, _) | Protocol::Onion3(_) => Some(TransportKind::Onion),
+                Protocol::P2pCircuit => Some(TransportKind::Circuit),
+                _ => transport,
+            };
+            match p {
+                Protocol::Tls => tls = true,
+                Protocol::P2pCircuit => tls = false,
+                _ => {}
+            }
+        }
+        transport
+    }
+
+    /// Checks whether this multiaddress is reached through a circuit relay.
+    pub fn is_relayed(&self) -> bool {
+        self.iter().any(|p| p == Protocol::P2pCircuit)
+    }
+
+    /// Checks whether a web browser can dial this multiaddress.
+    ///
+    /// Browsers can dial secure WebSockets, WebTransport, WebRTC-direct and browser-to-browser
+    /// WebRTC. Plain WebSockets are excluded, as browsers block them from secure contexts.
+    pub fn is_browser_dialable(&self) -> bool {
+        matches!(
+            self.transport(),
+            Some(
+                TransportKind::WebSocket { secure: true }
+                    | TransportKind::WebTransport
+                    | TransportKind::WebRTCDirect
+                    | TransportKind::WebRTC
+            )
+        )
+    }
+
+    /// Checks whether connections to this multiaddress are secured by TLS.
+    ///
+    /// This is the case for an explicit `/tls`, the deprecated `/https` and `/wss` aliases,
+    /// and for QUIC, which always runs TLS 1.3.
+    pub fn uses_tls(&self) -> bool {
+        self.iter().any(|p| {
+            matches!(
+                p,
+                Protocol::Tls
+                    | Protocol::Https
+                    | Protocol::Wss(_)
+                    | Protocol::Quic
+                    | Protocol::QuicV1
+            )
+        })
+    }
+}
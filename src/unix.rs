@@ -0,0 +1,218 @@
+use crate::{Multiaddr, MultiaddrSlice, Protocol};
+#[cfg(target_os = "android")]
+use std::os::android::net::SocketAddrExt;
+#[cfg(target_os = "linux")]
+use std::os::linux::net::SocketAddrExt;
+#[cfg(unix)]
+use std::os::unix::net::SocketAddr as UnixSocketAddr;
+use std::{
+    convert::TryFrom,
+    error, fmt,
+    path::{Path, PathBuf},
+};
+
+impl Multiaddr {
+    /// Creates a multiaddress of the form `/unix/<path>` from the path of a Unix domain
+    /// socket.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use multiaddr::Multiaddr;
+    ///
+    /// let addr = Multiaddr::from_unix_path("/tmp/node.sock").unwrap();
+    /// assert_eq!(addr.to_string(), "/unix/%2Ftmp%2Fnode.sock");
+    /// ```
+    ///
+    pub fn from_unix_path(path: impl AsRef<Path>) -> Result<Multiaddr, UnixAddrErr> {
+        let path = path.as_ref().to_str().ok_or(UnixAddrErr::NotUtf8)?;
+        if path.is_empty() {
+            return Err(UnixAddrErr::Unnamed);
+        }
+        if path.starts_with('\0') {
+            return Err(UnixAddrErr::Abstract);
+        }
+        Ok(Multiaddr::from(Protocol::Unix(path.to_owned().into())))
+    }
+
+    /// Creates a multiaddress of the form `/unix/<path>` from the address of a Unix domain
+    /// socket.
+    ///
+    /// On Linux and Android, the name of a socket in the abstract namespace is encoded as a
+    /// path starting with a NUL byte, like in the `sun_path` of the socket address.
+    #[cfg(unix)]
+    pub fn from_unix_socket_addr(addr: &UnixSocketAddr) -> Result<Multiaddr, UnixAddrErr> {
+        if let Some(path) = addr.as_pathname() {
+            return Multiaddr::from_unix_path(path);
+        }
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        if let Some(name) = addr.as_abstract_name() {
+            let name = std::str::from_utf8(name).map_err(|_| UnixAddrErr::NotUtf8)?;
+            return Ok(Multiaddr::from(Protocol::Unix(format!("\0{name}").into())));
+        }
+        Err(UnixAddrErr::Unnamed)
+    }
+}
+
+impl MultiaddrSlice {
+    /// Splits this multiaddress into the path of the Unix domain socket described by its
+    /// leading `/unix` component, and the rest of the address.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use multiaddr::Multiaddr;
+    /// use std::path::Path;
+    ///
+    /// let addr: Multiaddr = "/unix/%2Ftmp%2Fnode.sock/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC"
+    ///     .parse()
+    ///     .unwrap();
+    /// let (path, rest) = addr.to_unix_path().unwrap();
+    /// assert_eq!(path, Path::new("/tmp/node.sock"));
+    /// assert_eq!(rest.protocol_stack().collect::<Vec<_>>(), ["p2p"]);
+    /// ```
+    ///
+    pub fn to_unix_path(&self) -> Result<(PathBuf, Multiaddr), UnixAddrErr> {
+        let mut iter = self.iter();
+        let path = match iter.next().ok_or(UnixAddrErr::Incomplete)? {
+            Protocol::Unix(path) if path.starts_with('\0') => return Err(UnixAddrErr::Abstract),
+            Protocol::Unix(path) if path.is_empty() => return Err(UnixAddrErr::Unnamed),
+            Protocol::Unix(path) => PathBuf::from(path.into_owned()),
+            p => return Err(UnixAddrErr::unexpected(0, &p)),
+        };
+        Ok((path, iter.collect()))
+    }
+
+    /// Converts a multiaddress consisting only of a `/unix` component into the address of a
+    /// Unix domain socket.
+    ///
+    /// On Linux and Android, a path starting with a NUL byte is the name of a socket in the
+    /// abstract namespace, see [`Multiaddr::from_unix_socket_addr`].
+    #[cfg(unix)]
+    pub fn to_unix_socket_addr(&self) -> Result<UnixSocketAddr, UnixAddrErr> {
+        let mut iter = self.iter();
+        let addr = match iter.next().ok_or(UnixAddrErr::Incomplete)? {
+            Protocol::Unix(path) => unix_socket_addr(&path)?,
+            p => return Err(UnixAddrErr::unexpected(0, &p)),
+        };
+        match iter.next() {
+            None => Ok(addr),
+            Some(p) => Err(UnixAddrErr::unexpected(1, &p)),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn unix_socket_addr(path: &str) -> Result<UnixSocketAddr, UnixAddrErr> {
+    match path.strip_prefix('\0') {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        Some(name) => {
+            UnixSocketAddr::from_abstract_name(name).map_err(|_| UnixAddrErr::InvalidPath)
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        Some(_) => Err(UnixAddrErr::Abstract),
+        None if path.is_empty() => Err(UnixAddrErr::Unnamed),
+        None => UnixSocketAddr::from_pathname(path).map_err(|_| UnixAddrErr::InvalidPath),
+    }
+}
+
+impl TryFrom<&Path> for Multiaddr {
+    type Error = UnixAddrErr;
+
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        Multiaddr::from_unix_path(path)
+    }
+}
+
+impl TryFrom<PathBuf> for Multiaddr {
+    type Error = UnixAddrErr;
+
+    fn try_from(path: PathBuf) -> Result<Self, Self::Error> {
+        Multiaddr::from_unix_path(path)
+    }
+}
+
+impl TryFrom<&Multiaddr> for PathBuf {
+    type Error = UnixAddrErr;
+
+    /// Converts a multiaddress consisting only of a `/unix` component.
+    ///
+    /// Use [`MultiaddrSlice::to_unix_path`] to convert addresses with additional components.
+    fn try_from(addr: &Multiaddr) -> Result<Self, Self::Error> {
+        let (path, rest) = addr.to_unix_path()?;
+        match rest.iter().next() {
+            None => Ok(path),
+            Some(p) => Err(UnixAddrErr::unexpected(1, &p)),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl TryFrom<&UnixSocketAddr> for Multiaddr {
+    type Error = UnixAddrErr;
+
+    fn try_from(addr: &UnixSocketAddr) -> Result<Self, Self::Error> {
+        Multiaddr::from_unix_socket_addr(addr)
+    }
+}
+
+#[cfg(unix)]
+impl TryFrom<&Multiaddr> for UnixSocketAddr {
+    type Error = UnixAddrErr;
+
+    fn try_from(addr: &Multiaddr) -> Result<Self, Self::Error> {
+        addr.to_unix_socket_addr()
+    }
+}
+
+/// Error while converting between a multiaddress and the path or address of a Unix domain
+/// socket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnixAddrErr {
+    /// The multiaddress is empty.
+    Incomplete,
+    /// The component at `index` cannot be part of a Unix socket address at its position.
+    UnexpectedProtocol {
+        /// Index of the offending component.
+        index: usize,
+        /// Tag of the offending component, see [`Protocol::tag`].
+        tag: &'static str,
+    },
+    /// The path is not valid UTF-8, which `/unix` requires.
+    NotUtf8,
+    /// The path is empty, or the socket address is unnamed.
+    Unnamed,
+    /// The socket is in the abstract namespace, which has no path and is only supported on
+    /// Linux and Android.
+    Abstract,
+    /// The path is too long for a socket address, or contains a NUL byte.
+    InvalidPath,
+}
+
+impl UnixAddrErr {
+    fn unexpected(index: usize, p: &Protocol<'_>) -> Self {
+        UnixAddrErr::UnexpectedProtocol {
+            index,
+            tag: p.tag(),
+        }
+    }
+}
+
+impl fmt::Display for UnixAddrErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnixAddrErr::Incomplete => f.write_str("multiaddr does not contain a unix path"),
+            UnixAddrErr::UnexpectedProtocol { index, tag } => {
+                write!(f, "unexpected protocol `{tag}` at component {index}")
+            }
+            UnixAddrErr::NotUtf8 => f.write_str("unix socket path is not valid UTF-8"),
+            UnixAddrErr::Unnamed => f.write_str("unix socket has no path"),
+            UnixAddrErr::Abstract => {
+                f.write_str("unix socket in the abstract namespace is not supported here")
+            }
+            UnixAddrErr::InvalidPath => f.write_str("invalid unix socket path"),
+        }
+    }
+}
+
+impl error::Error for UnixAddrErr {}
@@ -0,0 +1,264 @@
+use crate::{Error, Multiaddr, MultiaddrSlice, Protocol};
+use alloc::vec::Vec;
+use core::{error, fmt};
+
+/// Hosts can start an address, or the destination part of a circuit relay address.
+const AFTER_CIRCUIT: &[&str] = &["p2p-circuit"];
+/// Protocols that can carry a port.
+const IP_OR_DNS: &[&str] = &["ip4", "ip6", "dns", "dns4", "dns6"];
+
+/// For each protocol, the protocols it may directly follow and whether it may start an
+/// address. Protocols not listed here may appear anywhere.
+const FOLLOWS: &[(&str, &[&str], bool)] = &[
+    ("ip4", AFTER_CIRCUIT, true),
+    ("ip6", &["ip6zone", "p2p-circuit"], true),
+    ("ip6zone", AFTER_CIRCUIT, true),
+    ("ipcidr", &["ip4", "ip6"], false),
+    ("dns", AFTER_CIRCUIT, true),
+    ("dns4", AFTER_CIRCUIT, true),
+    ("dns6", AFTER_CIRCUIT, true),
+    ("dnsaddr", AFTER_CIRCUIT, true),
+    ("onion", AFTER_CIRCUIT, true),
+    ("onion3", AFTER_CIRCUIT, true),
+    ("garlic64", AFTER_CIRCUIT, true),
+    ("garlic32", AFTER_CIRCUIT, true),
+    ("memory", &[], true),
+    ("unix", &[], true),
+    ("tcp", IP_OR_DNS, false),
+    ("udp", IP_OR_DNS, false),
+    ("dccp", IP_OR_DNS, false),
+    ("sctp", IP_OR_DNS, false),
+    ("udt", &["udp"], false),
+    ("utp", &["udp"], false),
+    ("quic", &["udp"], false),
+    ("quic-v1", &["udp"], false),
+    ("webtransport", &["quic", "quic-v1"], false),
+    ("webrtc-direct", &["udp"], false),
+    ("webrtc", AFTER_CIRCUIT, true),
+    (
+        "certhash",
+        &["webtransport", "webrtc-direct", "certhash"],
+        false,
+    ),
+    ("tls", &["tcp"], false),
+    ("sni", &["tls"], false),
+    (
+        "noise",
+        &["tcp", "ws", "wss", "x-parity-ws", "x-parity-wss"],
+        false,
+    ),
+    ("http", &["tcp", "tls", "sni"], false),
+    ("https", &["tcp"], false),
+    ("http-path", &["http", "https", "ws", "wss"], false),
+    ("ws", &["tcp", "tls", "sni"], false),
+    ("x-parity-ws", &["tcp", "tls", "sni"], false),
+    ("wss", &["tcp"], false),
+    ("x-parity-wss", &["tcp"], false),
+    ("p2p-circuit", &["p2p"], true),
+];
+
+impl MultiaddrSlice {
+    /// Checks the order of the components of this multiaddress and the constraints on
+    /// their values, returning all violations found.
+    ///
+    /// Parsing a multiaddress only checks each component on its own. This additionally
+    /// checks, amongst others, that
+    ///
+    /// - `/ip4`, `/ip6`, `/dns*` and other hosts start the address or follow a `/p2p-circuit`,
+    /// - ports such as `/tcp` follow a host and `/quic-v1`, `/webrtc-direct` follow `/udp`,
+    /// - `/certhash` follows `/webtransport` or `/webrtc-direct` and `/sni` follows `/tls`,
+    /// - `/ip6zone` is followed by `/ip6`,
+    /// - `/p2p` is not directly followed by another `/p2p`,
+    /// - `/ipcidr` prefixes fit the preceding address and names and paths are not empty.
+    ///
+    /// Protocols registered at runtime are not checked.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use multiaddr::{Multiaddr, Rule};
+    ///
+    /// let addr: Multiaddr = "/ip4/1.2.3.4/udp/443/quic-v1/webtransport".parse().unwrap();
+    /// assert!(addr.validate().is_ok());
+    ///
+    /// let addr: Multiaddr = "/tcp/80/ip4/1.2.3.4".parse().unwrap();
+    /// let violations = addr.validate().unwrap_err();
+    /// assert_eq!(violations[0].index, 0);
+    /// assert_eq!(violations[0].tag, "tcp");
+    /// assert!(matches!(violations[0].rule, Rule::Follows { .. }));
+    /// ```
+    ///
+    pub fn validate(&self) -> Result<(), Vec<Violation>> {
+        let mut violations = Vec::new();
+        let mut prev: Option<Protocol<'_>> = None;
+        let mut iter = self.iter().enumerate().peekable();
+        while let Some((index, p)) = iter.next() {
+            let tag = p.tag();
+            let mut violation = |rule| violations.push(Violation { index, tag, rule });
+
+            let prev_tag = prev.as_ref().map(Protocol::tag);
+            if let Some((_, after, at_start)) = FOLLOWS.iter().find(|(t, _, _)| *t == tag) {
+                let allowed = match prev_tag {
+                    None => *at_start,
+                    Some(prev) => after.contains(&prev),
+                };
+                if !allowed {
+                    violation(Rule::Follows {
+                        after,
+                        at_start: *at_start,
+                    });
+                }
+            }
+            if let (Protocol::P2p(_), Some("p2p")) = (&p, prev_tag) {
+                violation(Rule::NotAfter(&["p2p"]));
+            }
+
+            match &p {
+                Protocol::Ip6zone(_) if !matches!(iter.peek(), Some((_, Protocol::Ip6(_)))) => {
+                    violation(Rule::Precedes(&["ip6"]))
+                }
+                Protocol::Ipcidr(prefix) => {
+                    let max = match prev {
+                        Some(Protocol::Ip4(_)) => 32,
+                        _ => 128,
+                    };
+                    if *prefix > max {
+                        violation(Rule::PrefixTooLong { max })
+                    }
+                }
+                Protocol::Dns(s)
+                | Protocol::Dns4(s)
+                | Protocol::Dns6(s)
+                | Protocol::Dnsaddr(s)
+                | Protocol::Sni(s)
+                | Protocol::Ip6zone(s)
+                | Protocol::Unix(s)
+                    if s.is_empty() =>
+                {
+                    violation(Rule::EmptyValue)
+                }
+                _ => {}
+            }
+            prev = Some(p);
+        }
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+impl Multiaddr {
+    /// Parses a multiaddress and [validates](MultiaddrSlice::validate) it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use multiaddr::{Multiaddr, StrictParseError};
+    ///
+    /// assert!(Multiaddr::from_str_strict("/ip4/1.2.3.4/tcp/80").is_ok());
+    /// assert!(matches!(
+    ///     Multiaddr::from_str_strict("/ip4/1.2.3.4/sni/example.com"),
+    ///     Err(StrictParseError::Invalid(_))
+    /// ));
+    /// ```
+    ///
+    pub fn from_str_strict(input: &str) -> Result<Multiaddr, StrictParseError> {
+        let addr: Multiaddr = input.parse().map_err(StrictParseError::Parse)?;
+        addr.validate().map_err(StrictParseError::Invalid)?;
+        Ok(addr)
+    }
+}
+
+/// A violation of a [`Rule`] by a component of a multiaddress, see
+/// [`MultiaddrSlice::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// Index of the offending component.
+    pub index: usize,
+    /// Tag of the offending component, see [`Protocol::tag`].
+    pub tag: &'static str,
+    /// The rule the component violates.
+    pub rule: Rule,
+}
+
+/// A rule a component of a multiaddress has to follow, see [`MultiaddrSlice::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Rule {
+    /// The component must directly follow one of the protocols in `after`, or start the
+    /// address if `at_start` is set.
+    Follows {
+        after: &'static [&'static str],
+        at_start: bool,
+    },
+    /// The component must not directly follow any of these protocols.
+    NotAfter(&'static [&'static str]),
+    /// The component must be directly followed by one of these protocols.
+    Precedes(&'static [&'static str]),
+    /// The `/ipcidr` prefix is longer than the preceding IP address.
+    PrefixTooLong { max: u8 },
+    /// The value of the component must not be empty.
+    EmptyValue,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Violation { index, tag, rule } = self;
+        write!(f, "`{tag}` at component {index} ")?;
+        match rule {
+            Rule::Follows { after, at_start } => {
+                let after = after.join("`, `");
+                match (after.is_empty(), at_start) {
+                    (true, true) => f.write_str("must start the multiaddr"),
+                    (true, false) => f.write_str("is not allowed here"),
+                    (false, true) => write!(f, "must start the multiaddr or follow `{after}`"),
+                    (false, false) => write!(f, "must follow `{after}`"),
+                }
+            }
+            Rule::NotAfter(after) => write!(f, "must not follow `{}`", after.join("`, `")),
+            Rule::Precedes(before) => write!(f, "must be followed by `{}`", before.join("`, `")),
+            Rule::PrefixTooLong { max } => write!(f, "must not be longer than {max}"),
+            Rule::EmptyValue => f.write_str("must not be empty"),
+        }
+    }
+}
+
+impl error::Error for Violation {}
+
+/// Error of [`Multiaddr::from_str_strict`].
+#[derive(Debug)]
+pub enum StrictParseError {
+    /// The string is not a multiaddress.
+    Parse(Error),
+    /// The multiaddress violates the rules checked by [`MultiaddrSlice::validate`].
+    Invalid(Vec<Violation>),
+}
+
+impl fmt::Display for StrictParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StrictParseError::Parse(e) => e.fmt(f),
+            StrictParseError::Invalid(violations) => {
+                f.write_str("invalid multiaddr")?;
+                for (i, violation) in violations.iter().enumerate() {
+                    f.write_str(if i == 0 { ": " } else { "; " })?;
+                    violation.fmt(f)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl error::Error for StrictParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            StrictParseError::Parse(e) => Some(e),
+            StrictParseError::Invalid(violations) => violations
+                .first()
+                .map(|v| v as &(dyn error::Error + 'static)),
+        }
+    }
+}
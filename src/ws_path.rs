@@ -0,0 +1,119 @@
+use crate::{Multiaddr, MultiaddrSlice, Protocol};
+use alloc::{borrow::Cow, format};
+
+/// How a WebSocket path other than `/` is encoded, see [`Multiaddr::with_ws_path`] and
+/// [`MultiaddrSlice::to_ws_path_encoding`].
+///
+/// Both forms are always decoded: the legacy form into a single [`Protocol::Ws`] or
+/// [`Protocol::Wss`] carrying the path, and the standard form into a `Ws("/")` or `Wss("/")`
+/// followed by a [`Protocol::HttpPath`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WsPathEncoding {
+    /// The non-standard `/x-parity-ws/<path>` and `/x-parity-wss/<path>`, with the codes
+    /// `4770` and `4780`, understood by older versions of this crate.
+    Legacy,
+    /// `/ws/http-path/<path>` and `/wss/http-path/<path>`, as specified by the multiaddr
+    /// registry and understood by other implementations. The leading `/` of the path is
+    /// implied by `/http-path`.
+    Standard,
+}
+
+impl Multiaddr {
+    /// Appends a `/ws` with the given path, encoded as chosen by `encoding`.
+    ///
+    /// A path of `/` or the empty path appends a plain `/ws` in both encodings.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use multiaddr::{Multiaddr, WsPathEncoding};
+    ///
+    /// let addr: Multiaddr = "/ip4/1.2.3.4/tcp/80".parse().unwrap();
+    /// assert_eq!(
+    ///     addr.clone().with_ws_path("/chat", WsPathEncoding::Standard).to_string(),
+    ///     "/ip4/1.2.3.4/tcp/80/ws/http-path/chat"
+    /// );
+    /// assert_eq!(
+    ///     addr.with_ws_path("/chat", WsPathEncoding::Legacy).to_string(),
+    ///     "/ip4/1.2.3.4/tcp/80/x-parity-ws/%2Fchat"
+    /// );
+    /// ```
+    ///
+    pub fn with_ws_path(mut self, path: &str, encoding: WsPathEncoding) -> Self {
+        let path = strip_slash(path);
+        match encoding {
+            WsPathEncoding::Standard => push_standard(&mut self, Protocol::Ws, path),
+            WsPathEncoding::Legacy => self.push(Protocol::Ws(Cow::Owned(format!("/{path}")))),
+        }
+        self
+    }
+}
+
+impl MultiaddrSlice {
+    /// Converts the WebSockets with a path in this multiaddress into the given encoding,
+    /// keeping all other components as they are.
+    ///
+    /// Unlike [`normalize`](MultiaddrSlice::normalize), this keeps `/wss` and does not touch
+    /// any other protocol, so the result can be sent to peers expecting either form.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use multiaddr::{Multiaddr, WsPathEncoding};
+    ///
+    /// let legacy: Multiaddr = "/dns/Example.com/tcp/443/x-parity-wss/%2Fchat".parse().unwrap();
+    /// let standard = legacy.to_ws_path_encoding(WsPathEncoding::Standard);
+    /// assert_eq!(standard.to_string(), "/dns/Example.com/tcp/443/wss/http-path/chat");
+    /// assert_eq!(standard.to_ws_path_encoding(WsPathEncoding::Legacy), legacy);
+    /// ```
+    ///
+    pub fn to_ws_path_encoding(&self, encoding: WsPathEncoding) -> Multiaddr {
+        let mut addr = Multiaddr::with_capacity(self.len());
+        let mut iter = self.iter().peekable();
+        while let Some(p) = iter.next() {
+            match (encoding, p) {
+                (WsPathEncoding::Standard, Protocol::Ws(path)) if path != "/" => {
+                    push_standard(&mut addr, Protocol::Ws, strip_slash(&path))
+                }
+                (WsPathEncoding::Standard, Protocol::Wss(path)) if path != "/" => {
+                    push_standard(&mut addr, Protocol::Wss, strip_slash(&path))
+                }
+                (WsPathEncoding::Legacy, p) if matches!(&p, Protocol::Ws(path) | Protocol::Wss(path) if path == "/") =>
+                {
+                    let path = match iter.peek() {
+                        Some(Protocol::HttpPath(path)) => format!("/{path}"),
+                        _ => {
+                            addr.push(p);
+                            continue;
+                        }
+                    };
+                    iter.next();
+                    addr.push(match p {
+                        Protocol::Ws(_) => Protocol::Ws(Cow::Owned(path)),
+                        _ => Protocol::Wss(Cow::Owned(path)),
+                    });
+                }
+                (_, p) => addr.push(p),
+            }
+        }
+        addr
+    }
+}
+
+fn strip_slash(path: &str) -> &str {
+    path.strip_prefix('/').unwrap_or(path)
+}
+
+/// Pushes `ws("/")`, followed by an `/http-path` with the given path unless it is empty.
+///
+/// `path` is without its leading `/`.
+pub(crate) fn push_standard(
+    addr: &mut Multiaddr,
+    ws: impl FnOnce(Cow<'static, str>) -> Protocol<'static>,
+    path: &str,
+) {
+    addr.push(ws(Cow::Borrowed("/")));
+    if !path.is_empty() {
+        addr.push(Protocol::HttpPath(Cow::Borrowed(path)));
+    }
+}
@@ -4,9 +4,9 @@ use multihash::Multihash;
 use quickcheck::{Arbitrary, Gen, QuickCheck};
 use std::{
     borrow::Cow,
-    convert::{TryFrom, TryInto},
+    convert::TryFrom,
     iter::{self, FromIterator},
-    net::{Ipv4Addr, Ipv6Addr},
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
     str::FromStr,
 };
 
@@ -56,6 +56,22 @@ fn push_pop_identity() {
     QuickCheck::new().quickcheck(prop as fn(Ma, Proto) -> bool)
 }
 
+#[test]
+fn protocol_kind() {
+    fn prop(p: Proto) -> bool {
+        match p.0.kind() {
+            Some(kind) => {
+                kind.code() == p.0.code()
+                    && kind.name() == p.0.tag()
+                    && ProtocolKind::try_from(kind.code()).ok() == Some(kind)
+                    && kind.name().parse::<ProtocolKind>().ok() == Some(kind)
+            }
+            None => matches!(p.0, Protocol::Custom { .. }),
+        }
+    }
+    QuickCheck::new().quickcheck(prop as fn(Proto) -> bool)
+}
+
 #[test]
 fn ends_with() {
     fn prop(Ma(m): Ma) {
@@ -80,6 +96,75 @@ fn starts_with() {
     QuickCheck::new().quickcheck(prop as fn(_))
 }
 
+#[test]
+fn slice_identity() {
+    fn prop(Ma(m): Ma) -> bool {
+        let bytes = m.to_vec();
+        let slice = MultiaddrSlice::from_bytes(&bytes).unwrap();
+        slice == &*m && slice.to_string() == m.to_string() && slice.to_owned() == m
+    }
+    QuickCheck::new().quickcheck(prop as fn(Ma) -> bool)
+}
+
+#[test]
+fn to_from_bytes_buf_identity() {
+    fn prop(Ma(m): Ma) -> bool {
+        let bytes = bytes::Bytes::from(m.to_vec());
+        Multiaddr::try_from(bytes).ok() == Some(m)
+    }
+    QuickCheck::new().quickcheck(prop as fn(Ma) -> bool)
+}
+
+#[test]
+fn socket_addr_identity() {
+    fn prop(addr: SocketAddr, tcp: bool) -> bool {
+        let transport = if tcp { Transport::Tcp } else { Transport::Udp };
+        let ma = Multiaddr::from_socket_addr(addr, transport);
+        let (socket, t, rest) = ma.to_socket_addr().unwrap();
+        let addr = match addr {
+            SocketAddr::V6(mut a) => {
+                a.set_flowinfo(0);
+                SocketAddr::V6(a)
+            }
+            a => a,
+        };
+        socket == addr && t == transport && rest.is_empty()
+    }
+    QuickCheck::new().quickcheck(prop as fn(SocketAddr, bool) -> bool)
+}
+
+#[test]
+fn valid_multiaddr_generator() {
+    fn prop(generators::ValidMultiaddr(m): generators::ValidMultiaddr) -> bool {
+        let relayed =
+            !m.is_relayed() || RelayedAddr::try_from(&m).map(Multiaddr::from).as_ref() == Ok(&m);
+        relayed && m.validate().is_ok() && m.to_string().parse::<Multiaddr>().ok() == Some(m)
+    }
+    QuickCheck::new().quickcheck(prop as fn(_) -> bool)
+}
+
+#[cfg(feature = "proptest")]
+proptest::proptest! {
+    #[test]
+    fn proptest_to_from_bytes_identity(m in generators::any_multiaddr()) {
+        proptest::prop_assert_eq!(Multiaddr::try_from(m.to_vec()).ok(), Some(m));
+    }
+
+    #[test]
+    fn proptest_valid_multiaddr_generator(m in generators::valid_multiaddr()) {
+        proptest::prop_assert!(m.validate().is_ok());
+        proptest::prop_assert_eq!(m.to_string().parse::<Multiaddr>().ok(), Some(m));
+    }
+
+    #[test]
+    fn proptest_push_pop_identity(m in generators::any_multiaddr(), p in generators::any_protocol()) {
+        let mut n = m.clone();
+        n.push(p.clone());
+        proptest::prop_assert_eq!(n.pop(), Some(p));
+        proptest::prop_assert_eq!(n, m);
+    }
+}
+
 // Arbitrary impls
 
 #[derive(PartialEq, Eq, Clone, Hash, Debug)]
@@ -96,119 +181,52 @@ impl Arbitrary for Ma {
 struct Proto(Protocol<'static>);
 
 impl Proto {
-    const IMPL_VARIANT_COUNT: u8 = 40;
+    const IMPL_VARIANT_COUNT: u8 = 46;
 }
 
 impl Arbitrary for Proto {
     fn arbitrary(g: &mut Gen) -> Self {
-        use Protocol::*;
-        match u8::arbitrary(g) % Proto::IMPL_VARIANT_COUNT {
-            0 => Proto(Dccp(Arbitrary::arbitrary(g))),
-            1 => Proto(Dns(Cow::Owned(SubString::arbitrary(g).0))),
-            2 => Proto(Dns4(Cow::Owned(SubString::arbitrary(g).0))),
-            3 => Proto(Dns6(Cow::Owned(SubString::arbitrary(g).0))),
-            4 => Proto(Dnsaddr(Cow::Owned(SubString::arbitrary(g).0))),
-            5 => Proto(Http),
-            6 => Proto(Https),
-            7 => Proto(Ip4(Ipv4Addr::arbitrary(g))),
-            8 => Proto(Ip6(Ipv6Addr::arbitrary(g))),
-            9 => Proto(P2pWebRtcDirect),
-            10 => Proto(P2pWebRtcStar),
-            11 => Proto(WebRTCDirect),
-            12 => Proto(Certhash(Mh::arbitrary(g).0)),
-            13 => Proto(P2pWebSocketStar),
-            14 => Proto(Memory(Arbitrary::arbitrary(g))),
-            15 => {
-                let a = iter::repeat_with(|| u8::arbitrary(g))
-                    .take(10)
-                    .collect::<Vec<_>>()
-                    .try_into()
-                    .unwrap();
-                Proto(Onion(Cow::Owned(a), std::cmp::max(1, u16::arbitrary(g))))
-            }
-            16 => {
-                let a: [u8; 35] = iter::repeat_with(|| u8::arbitrary(g))
-                    .take(35)
-                    .collect::<Vec<_>>()
-                    .try_into()
-                    .unwrap();
-                Proto(Onion3((a, std::cmp::max(1, u16::arbitrary(g))).into()))
-            }
-            17 => Proto(P2p(PId::arbitrary(g).0)),
-            18 => Proto(P2pCircuit),
-            19 => Proto(Quic),
-            20 => Proto(QuicV1),
-            21 => Proto(Sctp(Arbitrary::arbitrary(g))),
-            22 => Proto(Tcp(Arbitrary::arbitrary(g))),
-            23 => Proto(Tls),
-            24 => Proto(Noise),
-            25 => Proto(Udp(Arbitrary::arbitrary(g))),
-            26 => Proto(Udt),
-            27 => Proto(Unix(Cow::Owned(SubString::arbitrary(g).0))),
-            28 => Proto(Utp),
-            29 => Proto(WebTransport),
-            30 => Proto(Ws("/".into())),
-            31 => Proto(Wss("/".into())),
-            32 => Proto(Ip6zone(Cow::Owned(SubString::arbitrary(g).0))),
-            33 => Proto(Ipcidr(Arbitrary::arbitrary(g))),
-            34 => {
-                let len = usize::arbitrary(g) % (462 - 387) + 387;
-                let a = iter::repeat_with(|| u8::arbitrary(g))
-                    .take(len)
-                    .collect::<Vec<_>>();
-                Proto(Garlic64(Cow::Owned(a)))
-            }
-            35 => {
-                let len = if bool::arbitrary(g) {
-                    32
-                } else {
-                    usize::arbitrary(g) % 128 + 35
-                };
-                let a = iter::repeat_with(|| u8::arbitrary(g))
-                    .take(len)
-                    .collect::<Vec<_>>();
-                Proto(Garlic32(Cow::Owned(a)))
-            }
-            36 => Proto(Sni(Cow::Owned(SubString::arbitrary(g).0))),
-            37 => Proto(P2pStardust),
-            38 => Proto(WebRTC),
-            39 => Proto(HttpPath(Cow::Owned(SubString::arbitrary(g).0))),
-            _ => panic!("outside range"),
+        // The generators of the crate cover all variants but `Custom`.
+        if u8::arbitrary(g) % Proto::IMPL_VARIANT_COUNT != 0 {
+            return Proto(Protocol::arbitrary(g));
         }
+        let p = *g.choose(&custom_protocols()).unwrap();
+        let value = match p.codec {
+            ValueCodec::None => Vec::new(),
+            ValueCodec::Fixed(n) => iter::repeat_with(|| u8::arbitrary(g)).take(n).collect(),
+            _ => Vec::arbitrary(g),
+        };
+        Proto(Protocol::Custom {
+            code: p.code,
+            name: p.name,
+            value: Cow::Owned(value),
+        })
     }
 }
 
-#[derive(Clone, Debug)]
-struct Mh(Multihash<64>);
-
-impl Arbitrary for Mh {
-    fn arbitrary(g: &mut Gen) -> Self {
-        let mut hash: [u8; 32] = [0; 32];
-        hash.fill_with(|| u8::arbitrary(g));
-        Mh(Multihash::wrap(0x0, &hash).expect("The digest size is never too large"))
-    }
-}
-
-#[derive(Clone, Debug)]
-struct PId(PeerId);
-
-impl Arbitrary for PId {
-    fn arbitrary(g: &mut Gen) -> Self {
-        let mh = Mh::arbitrary(g);
-
-        PId(PeerId::from_multihash(mh.0).expect("identity multihash works if digest size < 64"))
-    }
-}
-
-#[derive(PartialEq, Eq, Clone, Debug)]
-struct SubString(String); // ASCII string without '/'
-
-impl Arbitrary for SubString {
-    fn arbitrary(g: &mut Gen) -> Self {
-        let mut s = String::arbitrary(g);
-        s.retain(|c| c.is_ascii() && c != '/');
-        SubString(s)
+/// Registers and returns the custom protocols used by the `Arbitrary` impls.
+fn custom_protocols() -> [CustomProtocol; 3] {
+    let protocols = [
+        CustomProtocol {
+            code: 0x3f0000,
+            name: "x-test-none",
+            codec: ValueCodec::None,
+        },
+        CustomProtocol {
+            code: 0x3f0001,
+            name: "x-test-fixed",
+            codec: ValueCodec::Fixed(4),
+        },
+        CustomProtocol {
+            code: 0x3f0002,
+            name: "x-test-prefixed",
+            codec: ValueCodec::LengthPrefixed,
+        },
+    ];
+    for p in protocols {
+        register_protocol(p).unwrap();
     }
+    protocols
 }
 
 // other unit tests
@@ -501,6 +519,38 @@ fn construct_success() {
             Certhash(Multihash::from_bytes(&decoded).unwrap()),
         ],
     );
+
+    ma_valid(
+        "/scion/1-ff00:0:110/ip4/127.0.0.1/udp/30041/quic-v1",
+        "80C0C0060C312D666630303A303A313130047F00000191027559CD03",
+        vec![Scion("1-ff00:0:110".into()), Ip4(local), Udp(30041), QuicV1],
+    );
+    let key: Vec<u8> = (0..32).collect();
+    ma_valid(
+        "/ip4/127.0.0.1/tcp/8008/shs/uAAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8",
+        "047F000001061F48C80320000102030405060708090A0B0C0D0E0F101112131415161718191A1B1C1D1E1F",
+        vec![Ip4(local), Tcp(8008), Shs(key.into())],
+    );
+    ma_valid(
+        "/ip4/127.0.0.1/tcp/1234/plaintextv2",
+        "047F0000010604D2E1D8C103",
+        vec![Ip4(local), Tcp(1234), PlaintextV2],
+    );
+    ma_valid(
+        "/ip6/::1/udp/1234/quic-v1/silverpine",
+        "2900000000000000000000000000000001910204D2CD03C27E",
+        vec![Ip6("::1".parse().unwrap()), Udp(1234), QuicV1, Silverpine],
+    );
+    let id = [&[0x01, 0x55][..], &(0..32).collect::<Vec<u8>>()].concat();
+    ma_valid(
+        "/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/thread/bafkqaaicamcakbqhbaequcymbuha6earcijrifiwc4mbsgq3dqor4hy",
+        "A503221220D52EBB89D85B02A284948203A62FF28389C57C9F42BEEC4EC20DB76A68911C0B960322015500010203\
+         0405060708090A0B0C0D0E0F101112131415161718191A1B1C1D1E1F",
+        vec![
+            P2p(peer_id("QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC")),
+            Thread(id.into()),
+        ],
+    );
 }
 
 #[test]
@@ -595,12 +645,117 @@ fn to_multiaddr() {
     );
 }
 
+#[test]
+fn socket_addr_conversions() {
+    let addr: SocketAddr = "[fe80::1%3]:443".parse().unwrap();
+    let ma = Multiaddr::from_socket_addr(addr, Transport::Udp);
+    assert_eq!(ma, "/ip6zone/3/ip6/fe80::1/udp/443".parse().unwrap());
+    assert_eq!(SocketAddr::try_from(&ma), Ok(addr));
+
+    let ma: Multiaddr = "/ip6/fe80::1/ip6zone/3/tcp/443".parse().unwrap();
+    assert_eq!(SocketAddr::try_from(&ma), Ok(addr));
+
+    let ma: Multiaddr =
+        "/ip4/127.0.0.1/tcp/80/ws/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC"
+            .parse()
+            .unwrap();
+    let (socket, transport, rest) = ma.to_socket_addr().unwrap();
+    assert_eq!(socket, "127.0.0.1:80".parse().unwrap());
+    assert_eq!(transport, Transport::Tcp);
+    assert_eq!(
+        rest,
+        "/ws/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC"
+            .parse()
+            .unwrap()
+    );
+    assert_eq!(
+        SocketAddr::try_from(&ma),
+        Err(SocketAddrErr::UnexpectedProtocol {
+            index: 2,
+            tag: "ws"
+        })
+    );
+}
+
+#[test]
+fn socket_addr_conversion_fail() {
+    let cases = [
+        ("", SocketAddrErr::Incomplete),
+        ("/ip4/127.0.0.1", SocketAddrErr::Incomplete),
+        (
+            "/dns/example.com/tcp/80",
+            SocketAddrErr::UnexpectedProtocol {
+                index: 0,
+                tag: "dns",
+            },
+        ),
+        (
+            "/ip4/127.0.0.1/sctp/80",
+            SocketAddrErr::UnexpectedProtocol {
+                index: 1,
+                tag: "sctp",
+            },
+        ),
+        (
+            "/ip6zone/3/ip4/127.0.0.1/tcp/80",
+            SocketAddrErr::UnexpectedProtocol {
+                index: 1,
+                tag: "ip4",
+            },
+        ),
+        (
+            "/ip6zone/eth0/ip6/fe80::1/tcp/80",
+            SocketAddrErr::InvalidZone("eth0".to_owned()),
+        ),
+    ];
+    for (addr, err) in cases {
+        let ma: Multiaddr = addr.parse().unwrap();
+        assert_eq!(SocketAddr::try_from(&ma), Err(err), "{addr}");
+    }
+}
+
+#[test]
+fn decode_lenient() {
+    let valid: Multiaddr = "/ip4/1.2.3.4/udp/1234/quic-v1".parse().unwrap();
+    let (address, tail) = Multiaddr::decode_lenient(valid.as_ref());
+    assert_eq!(address, valid);
+    assert!(tail.is_none());
+
+    // Unknown protocol code 0x3f4001.
+    let mut bytes = valid.to_vec();
+    bytes.extend([0x81, 0x80, 0xfd, 0x01, 0x00]);
+    let (address, tail) = Multiaddr::decode_lenient(&bytes);
+    let tail = tail.unwrap();
+    assert_eq!(address, valid);
+    assert_eq!(tail.offset, valid.len());
+    assert_eq!(tail.code, Some(0x3f4001));
+    assert_eq!(tail.remainder, &bytes[valid.len()..]);
+    assert!(matches!(tail.error, Error::UnknownProtocolId(0x3f4001)));
+
+    // Known protocol code with a truncated value.
+    let mut bytes: Vec<u8> = "/ip4/1.2.3.4".parse::<Multiaddr>().unwrap().to_vec();
+    bytes.extend([0x06, 0x01]);
+    let (address, tail) = Multiaddr::decode_lenient(&bytes);
+    let tail = tail.unwrap();
+    assert_eq!(address, "/ip4/1.2.3.4".parse().unwrap());
+    assert_eq!(tail.code, Some(6));
+    assert!(matches!(tail.error, Error::DataLessThanLen));
+
+    // Invalid varint.
+    let (address, tail) = Multiaddr::decode_lenient(&[0xff]);
+    let tail = tail.unwrap();
+    assert!(address.is_empty());
+    assert_eq!(tail.offset, 0);
+    assert_eq!(tail.code, None);
+}
+
 #[test]
 fn from_bytes_fail() {
     let bytes = vec![1, 2, 3, 4];
     assert!(Multiaddr::try_from(bytes).is_err());
 }
 
+#[cfg(feature = "serde")]
 #[test]
 fn ser_and_deser_json() {
     let addr: Multiaddr = "/ip4/0.0.0.0/tcp/0/tls".parse::<Multiaddr>().unwrap();
@@ -610,6 +765,7 @@ fn ser_and_deser_json() {
     assert_eq!(addr, deserialized);
 }
 
+#[cfg(feature = "serde")]
 #[test]
 fn ser_and_deser_bincode() {
     let addr: Multiaddr = "/ip4/0.0.0.0/tcp/0/tls".parse::<Multiaddr>().unwrap();
@@ -623,6 +779,36 @@ fn ser_and_deser_bincode() {
     assert_eq!(addr, deserialized);
 }
 
+#[cfg(feature = "serde")]
+#[test]
+fn deser_borrowed_bincode() {
+    let addr: Multiaddr = "/ip4/0.0.0.0/tcp/0/tls".parse::<Multiaddr>().unwrap();
+    let serialized = bincode::serialize(&addr).unwrap();
+    let deserialized: &MultiaddrSlice = bincode::deserialize(&serialized).unwrap();
+    assert_eq!(&*addr, deserialized);
+    assert_eq!(bincode::serialize(deserialized).unwrap(), serialized);
+}
+
+#[test]
+fn slice_from_bytes_fail() {
+    assert!(MultiaddrSlice::from_bytes(&[1, 2, 3, 4]).is_err());
+    assert!(Multiaddr::try_from(bytes::Bytes::from_static(&[1, 2, 3, 4])).is_err());
+}
+
+#[test]
+fn slice_borrowing() {
+    let addr: Multiaddr = "/ip4/127.0.0.1/tcp/80/ws".parse().unwrap();
+    let bytes = addr.to_vec();
+    let slice = <&MultiaddrSlice>::try_from(&bytes[..4 + 1 + 3]).unwrap();
+    assert!(addr.starts_with(slice));
+    assert!(!addr.ends_with(slice));
+    assert_eq!(slice.protocol_stack().collect::<Vec<_>>(), ["ip4", "tcp"]);
+
+    let mut set = std::collections::HashSet::new();
+    set.insert(addr.clone());
+    assert!(set.contains(&*addr));
+}
+
 #[test]
 fn append() {
     let mut a: Multiaddr = Protocol::Ip4(Ipv4Addr::new(1, 2, 3, 4)).into();
@@ -671,12 +857,384 @@ fn replace_ip4_with_ip6() {
 fn unknown_protocol_string() {
     match "/unknown/1.2.3.4".parse::<Multiaddr>() {
         Ok(_) => panic!("The UnknownProtocolString error should be caused"),
-        Err(e) => match e {
+        Err(e @ crate::Error::InvalidComponentString { .. }) => match e.inner() {
             crate::Error::UnknownProtocolString(protocol) => {
                 assert_eq!(protocol, "unknown")
             }
             _ => panic!("The UnknownProtocolString error should be caused"),
         },
+        Err(e) => panic!("expected the position of the unknown protocol, got {e:?}"),
+    }
+}
+
+#[test]
+fn layer() {
+    let addr: Multiaddr = "/ip4/1.2.3.4/tcp/443/tls/sni/example.com/http/http-path/foo/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        addr.iter().map(|p| p.layer()).collect::<Vec<_>>(),
+        [
+            Layer::Network,
+            Layer::Transport,
+            Layer::Security,
+            Layer::Security,
+            Layer::Application,
+            Layer::Path,
+            Layer::PeerIdentity,
+            Layer::Relay,
+        ]
+    );
+    assert_eq!(Protocol::Wss("/".into()).layer(), Layer::Transport);
+    assert_eq!(Protocol::Https.layer(), Layer::Application);
+    assert_eq!(Protocol::Unix("/tmp/a".into()).layer(), Layer::Path);
+}
+
+#[test]
+fn transport_classification() {
+    use TransportKind::*;
+
+    // (address, transport, relayed, browser dialable, tls)
+    let cases = [
+        ("/ip4/1.2.3.4", None, false, false, false),
+        ("/ip4/1.2.3.4/tcp/80", Some(Tcp), false, false, false),
+        ("/ip4/1.2.3.4/tcp/80/tls", Some(Tcp), false, false, true),
+        ("/ip4/1.2.3.4/tcp/80/https", Some(Tcp), false, false, true),
+        ("/ip4/1.2.3.4/udp/80", Some(Udp), false, false, false),
+        ("/ip4/1.2.3.4/udp/80/quic", Some(Quic), false, false, true),
+        ("/ip4/1.2.3.4/udp/80/quic-v1", Some(QuicV1), false, false, true),
+        (
+            "/ip4/1.2.3.4/tcp/80/ws",
+            Some(WebSocket { secure: false }),
+            false,
+            false,
+            false,
+        ),
+        (
+            "/ip4/1.2.3.4/tcp/80/x-parity-ws/%2Ffoo",
+            Some(WebSocket { secure: false }),
+            false,
+            false,
+            false,
+        ),
+        (
+            "/dns/example.com/tcp/443/tls/sni/example.com/ws",
+            Some(WebSocket { secure: true }),
+            false,
+            true,
+            true,
+        ),
+        (
+            "/dns/example.com/tcp/443/wss",
+            Some(WebSocket { secure: true }),
+            false,
+            true,
+            true,
+        ),
+        (
+            "/dns/example.com/tcp/443/x-parity-wss/%2Ffoo",
+            Some(WebSocket { secure: true }),
+            false,
+            true,
+            true,
+        ),
+        (
+            "/ip4/1.2.3.4/udp/443/quic-v1/webtransport",
+            Some(WebTransport),
+            false,
+            true,
+            true,
+        ),
+        (
+            "/ip4/1.2.3.4/udp/443/webrtc-direct",
+            Some(WebRTCDirect),
+            false,
+            true,
+            false,
+        ),
+        (
+            "/ip4/1.2.3.4/tcp/80/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit/webrtc",
+            Some(WebRTC),
+            true,
+            true,
+            false,
+        ),
+        (
+            "/ip4/1.2.3.4/tcp/80/tls/ws/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit",
+            Some(Circuit),
+            true,
+            false,
+            true,
+        ),
+        ("/memory/1234", Some(Memory), false, false, false),
+        (
+            "/onion/aaimaq4ygg2iegci:80",
+            Some(Onion),
+            false,
+            false,
+            false,
+        ),
+    ];
+    for (addr, transport, relayed, browser, tls) in cases {
+        let addr: Multiaddr = addr.parse().unwrap();
+        assert_eq!(addr.transport(), transport, "{addr}");
+        assert_eq!(addr.is_relayed(), relayed, "{addr}");
+        assert_eq!(addr.is_browser_dialable(), browser, "{addr}");
+        assert_eq!(addr.uses_tls(), tls, "{addr}");
+    }
+    let unix = Multiaddr::from(Protocol::Unix("/tmp/a".into()));
+    assert_eq!(unix.transport(), Some(Unix));
+}
+
+#[test]
+fn scope() {
+    use Scope::*;
+
+    // One address per entry of the IANA special-purpose registries, plus the boundaries
+    // of the ranges that are commonly confused.
+    let cases = [
+        ("/ip4/0.0.0.0/tcp/0", Unspecified),
+        ("/ip4/0.1.2.3", Reserved),
+        ("/ip4/10.0.0.1", Private),
+        ("/ip4/100.63.255.255", Global),
+        ("/ip4/100.64.0.1", SharedAddressSpace),
+        ("/ip4/100.127.255.255", SharedAddressSpace),
+        ("/ip4/100.128.0.0", Global),
+        ("/ip4/127.0.0.1/udp/1234/quic-v1", Loopback),
+        ("/ip4/169.254.1.1", LinkLocal),
+        ("/ip4/172.15.255.255", Global),
+        ("/ip4/172.16.0.1", Private),
+        ("/ip4/172.31.255.255", Private),
+        ("/ip4/172.32.0.0", Global),
+        ("/ip4/192.0.0.1", Reserved),
+        ("/ip4/192.0.0.9", Global),
+        ("/ip4/192.0.0.10", Global),
+        ("/ip4/192.0.2.1", Documentation),
+        ("/ip4/192.88.99.1", Reserved),
+        ("/ip4/192.168.1.1", Private),
+        ("/ip4/198.18.0.1", Reserved),
+        ("/ip4/198.19.255.255", Reserved),
+        ("/ip4/198.51.100.1", Documentation),
+        ("/ip4/203.0.113.1", Documentation),
+        ("/ip4/224.0.0.251", Multicast),
+        ("/ip4/240.0.0.1", Reserved),
+        ("/ip4/255.255.255.255", Reserved),
+        ("/ip4/1.1.1.1/tcp/443", Global),
+        ("/ip4/8.8.8.8", Global),
+        ("/ip6/::", Unspecified),
+        ("/ip6/::1/tcp/80", Loopback),
+        ("/ip6/::ffff:127.0.0.1", Loopback),
+        ("/ip6/::ffff:10.1.2.3", Private),
+        ("/ip6/::ffff:8.8.8.8", Global),
+        ("/ip6/64:ff9b::808:808", Global),
+        ("/ip6/64:ff9b:1::1", Reserved),
+        ("/ip6/100::1", Reserved),
+        ("/ip6/2001::1", Reserved),
+        ("/ip6/2001:1::1", Global),
+        ("/ip6/2001:1::2", Global),
+        ("/ip6/2001:1::3", Reserved),
+        ("/ip6/2001:2::1", Reserved),
+        ("/ip6/2001:3::1", Global),
+        ("/ip6/2001:4:112::1", Global),
+        ("/ip6/2001:20::1", Global),
+        ("/ip6/2001:db8::1", Documentation),
+        ("/ip6/2002:101:101::1", Global),
+        ("/ip6/2606:4700:4700::1111", Global),
+        ("/ip6/3fff::1", Documentation),
+        ("/ip6/5f00::1", Reserved),
+        ("/ip6/fc00::1", UniqueLocal),
+        ("/ip6/fd12:3456::1", UniqueLocal),
+        ("/ip6/fe80::1", LinkLocal),
+        ("/ip6/febf::1", LinkLocal),
+        ("/ip6/fec0::1", Reserved),
+        ("/ip6/ff02::1", Multicast),
+        ("/ip6zone/eth0/ip6/fe80::1/tcp/80", LinkLocal),
+        ("/ip6/fe80::1/ip6zone/eth0/tcp/80", LinkLocal),
+        ("/dns/localhost/tcp/80", Loopback),
+        ("/dns4/LOCALHOST./tcp/80", Loopback),
+        ("/dns6/foo.localhost/tcp/80", Loopback),
+        ("/dns/printer.local/tcp/80", LinkLocal),
+        ("/dns/router.home.arpa/tcp/80", Private),
+        ("/dns/db.internal/tcp/80", Private),
+        ("/dns/notlocal/tcp/80", Global),
+        ("/dnsaddr/bootstrap.libp2p.io", Global),
+        ("/memory/1234", Host),
+        ("/tcp/80", Unknown),
+        ("/onion/aaimaq4ygg2iegci:80", Unknown),
+        (
+            "/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC",
+            Unknown,
+        ),
+    ];
+    for (addr, scope) in cases {
+        let addr: Multiaddr = addr.parse().unwrap();
+        assert_eq!(addr.scope(), scope, "{addr}");
+        assert_eq!(addr.is_globally_routable(), scope == Global, "{addr}");
+    }
+    assert_eq!(Multiaddr::empty().scope(), Unknown);
+    let unix = Multiaddr::from(Protocol::Unix("/tmp/a".into()));
+    assert_eq!(unix.scope(), Host);
+}
+
+#[test]
+fn custom_protocol() {
+    register_protocol(CustomProtocol {
+        code: 0x3f0100,
+        name: "x-custom-path",
+        codec: ValueCodec::Path,
+    })
+    .unwrap();
+    register_protocol(CustomProtocol {
+        code: 0x3f0101,
+        name: "x-custom-hex",
+        codec: ValueCodec::Fixed(2),
+    })
+    .unwrap();
+
+    let addr: Multiaddr = "/ip4/127.0.0.1/x-custom-hex/beef/x-custom-path/%2Ftmp%2Fa%20b%2Fc"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        addr.iter().collect::<Vec<_>>(),
+        vec![
+            Protocol::Ip4(Ipv4Addr::LOCALHOST),
+            Protocol::Custom {
+                code: 0x3f0101,
+                name: "x-custom-hex",
+                value: Cow::Borrowed(&[0xbe, 0xef]),
+            },
+            Protocol::Custom {
+                code: 0x3f0100,
+                name: "x-custom-path",
+                value: Cow::Borrowed(b"/tmp/a b/c"),
+            },
+        ]
+    );
+    assert_eq!(
+        addr.to_string(),
+        "/ip4/127.0.0.1/x-custom-hex/beef/x-custom-path/%2Ftmp%2Fa b%2Fc"
+    );
+    assert_eq!(Multiaddr::try_from(addr.to_vec()).unwrap(), addr);
+    #[cfg(feature = "serde")]
+    assert_eq!(
+        serde_json::from_str::<Multiaddr>(&serde_json::to_string(&addr).unwrap()).unwrap(),
+        addr
+    );
+    assert_eq!(
+        addr.protocol_stack().collect::<Vec<_>>(),
+        ["ip4", "x-custom-hex", "x-custom-path"]
+    );
+
+    assert!("/x-custom-hex/bee".parse::<Multiaddr>().is_err());
+    assert!("/x-custom-path".parse::<Multiaddr>().is_err());
+}
+
+#[test]
+fn custom_protocol_unregistered() {
+    let mut bytes = Vec::new();
+    Protocol::Tcp(80).write_bytes(&mut bytes).unwrap();
+    bytes.extend([0x80, 0x80, 0xfd, 0x01]);
+    assert!(matches!(
+        Multiaddr::try_from(bytes).unwrap_err().inner(),
+        Error::UnknownProtocolId(0x3f4000)
+    ));
+
+    let unregistered = Protocol::Custom {
+        code: 0x3f4000,
+        name: "x-unregistered",
+        value: Cow::Borrowed(&[]),
+    };
+    assert!(unregistered.write_bytes(&mut Vec::new()).is_err());
+
+    let mut addr = Multiaddr::from(Protocol::Tcp(80));
+    assert!(matches!(
+        addr.try_push(unregistered.clone()),
+        Err(Error::UnknownProtocolId(0x3f4000))
+    ));
+    assert_eq!(addr, Multiaddr::from(Protocol::Tcp(80)));
+    assert!(addr.clone().try_with(unregistered).is_err());
+
+    register_protocol(CustomProtocol {
+        code: 0x3f0102,
+        name: "x-custom-fixed",
+        codec: ValueCodec::Fixed(2),
+    })
+    .unwrap();
+    let wrong_length = Protocol::Custom {
+        code: 0x3f0102,
+        name: "x-custom-fixed",
+        value: Cow::Borrowed(&[1, 2, 3]),
+    };
+    assert!(matches!(
+        addr.try_push(wrong_length),
+        Err(Error::InvalidMultiaddr)
+    ));
+    assert_eq!(addr, Multiaddr::from(Protocol::Tcp(80)));
+    let right_length = Protocol::Custom {
+        code: 0x3f0102,
+        name: "x-custom-fixed",
+        value: Cow::Borrowed(&[1, 2]),
+    };
+    assert_eq!(
+        addr.try_with(right_length).unwrap().to_string(),
+        "/tcp/80/x-custom-fixed/0102"
+    );
+}
+
+#[test]
+#[should_panic]
+fn custom_protocol_unregistered_push() {
+    Multiaddr::empty().push(Protocol::Custom {
+        code: 0x3f4002,
+        name: "x-unregistered",
+        value: Cow::Borrowed(&[]),
+    });
+}
+
+#[test]
+fn register_protocol_conflicts() {
+    let custom = CustomProtocol {
+        code: 0x3f0200,
+        name: "x-conflict",
+        codec: ValueCodec::None,
+    };
+    assert_eq!(register_protocol(custom), Ok(()));
+    assert_eq!(register_protocol(custom), Ok(()));
+    assert_eq!(
+        register_protocol(CustomProtocol {
+            codec: ValueCodec::LengthPrefixed,
+            ..custom
+        }),
+        Err(RegisterError::CodeInUse(0x3f0200))
+    );
+    assert_eq!(
+        register_protocol(CustomProtocol {
+            code: 0x3f0201,
+            ..custom
+        }),
+        Err(RegisterError::NameInUse("x-conflict"))
+    );
+    assert_eq!(
+        register_protocol(CustomProtocol { code: 6, ..custom }),
+        Err(RegisterError::CodeInUse(6))
+    );
+    assert_eq!(
+        register_protocol(CustomProtocol {
+            code: 0x3f0202,
+            name: "ipfs",
+            ..custom
+        }),
+        Err(RegisterError::NameInUse("ipfs"))
+    );
+    for name in ["", "x/conflict", "x conflict", "x-conflict\n"] {
+        assert_eq!(
+            register_protocol(CustomProtocol {
+                code: 0x3f0203,
+                name,
+                ..custom
+            }),
+            Err(RegisterError::InvalidName(name))
+        );
     }
 }
 
@@ -831,3 +1389,1464 @@ mod multiaddr_with_p2p {
         )
     }
 }
+
+mod pattern {
+    use multiaddr::{patterns, Multiaddr, Pattern, PatternError, PeerId, Protocol};
+    use std::net::Ipv4Addr;
+
+    const PEER: &str = "QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC";
+    const RELAY: &str = "QmNnooDu7bfjPFoTZYxMNLWUQJyrVwtbZg5gBMjTezGAJN";
+    const CERTHASH: &str = "uEiDDq4_xNyDorZBH3TlGazyJdOWSwvo4PUo5YHFMrvDE8g";
+
+    fn ma(s: &str) -> Multiaddr {
+        s.replace("{peer}", PEER)
+            .replace("{relay}", RELAY)
+            .replace("{certhash}", CERTHASH)
+            .parse()
+            .unwrap()
+    }
+
+    fn assert_matches(pattern: &Pattern, valid: &[&str], invalid: &[&str]) {
+        for addr in valid {
+            assert!(pattern.matches(&ma(addr)), "{pattern} should match {addr}");
+        }
+        for addr in invalid {
+            assert!(
+                !pattern.matches(&ma(addr)),
+                "{pattern} should not match {addr}"
+            );
+        }
+    }
+
+    #[test]
+    fn syntax() {
+        let pattern: Pattern = "/{ip4|ip6|dns*}/tcp/*/ws?/p2p?".parse().unwrap();
+        assert_eq!(pattern.to_string(), "/{ip4|ip6|dns*}/tcp/*/ws?/p2p?");
+        assert_matches(
+            &pattern,
+            &[
+                "/ip4/1.2.3.4/tcp/80",
+                "/dnsaddr/example.com/tcp/80/ws",
+                "/ip6/::1/tcp/80/ws/p2p/{peer}",
+            ],
+            &[
+                "/ip4/1.2.3.4",
+                "/ip4/1.2.3.4/udp/80",
+                "/ip4/1.2.3.4/tcp/80/wss",
+                "/ip4/1.2.3.4/tcp/80/ws/ws",
+            ],
+        );
+
+        assert_eq!(
+            Pattern::new("ip4/tcp"),
+            Err(PatternError::MissingLeadingSlash("ip4/tcp".to_owned()))
+        );
+        assert_eq!(
+            Pattern::new("/ip4/tpc/80"),
+            Err(PatternError::InvalidElement("80".to_owned()))
+        );
+        assert_eq!(
+            Pattern::new("/{ip4|ipv6}"),
+            Err(PatternError::InvalidElement("{ip4|ipv6}".to_owned()))
+        );
+        assert_eq!(
+            Pattern::new("/tcp/<>"),
+            Err(PatternError::InvalidValue("<>".to_owned()))
+        );
+    }
+
+    #[test]
+    fn quantifiers_and_literals() {
+        let pattern: Pattern = "/ip4/127.0.0.1/udp/443/quic-v1/webtransport/certhash*/certhash"
+            .parse()
+            .unwrap();
+        assert_matches(
+            &pattern,
+            &[
+                "/ip4/127.0.0.1/udp/443/quic-v1/webtransport/certhash/{certhash}",
+                "/ip4/127.0.0.1/udp/443/quic-v1/webtransport/certhash/{certhash}/certhash/{certhash}",
+            ],
+            &[
+                "/ip4/127.0.0.1/udp/443/quic-v1/webtransport",
+                "/ip4/127.0.0.2/udp/443/quic-v1/webtransport/certhash/{certhash}",
+                "/ip4/127.0.0.1/udp/444/quic-v1/webtransport/certhash/{certhash}",
+            ],
+        );
+
+        let pattern: Pattern = "/dns+/.../p2p".parse().unwrap();
+        assert_matches(
+            &pattern,
+            &["/dns/a/p2p/{peer}", "/dns/a/dns/b/tcp/1/ws/p2p/{peer}"],
+            &["/p2p/{peer}", "/dns/a/tcp/1"],
+        );
+    }
+
+    #[test]
+    fn pathological() {
+        // Without memoisation, each `...` and `ip4*` tries every split of the remaining
+        // components, which takes exponential time before failing on the missing `/tcp`.
+        let addr = ma(&"/ip4/1.2.3.4".repeat(32));
+        let pattern: Pattern = format!("{}/tcp", "/...".repeat(32)).parse().unwrap();
+        assert!(!pattern.matches(&addr));
+        let pattern: Pattern = format!("{}/tcp", "/ip4*".repeat(32)).parse().unwrap();
+        assert!(!pattern.matches(&addr));
+
+        let pattern: Pattern = "/.../ip4*".repeat(32).parse().unwrap();
+        assert!(pattern.matches(&addr));
+    }
+
+    #[test]
+    fn captures() {
+        let pattern: Pattern = "/{ip4|dns*}/<host>/tcp/<port>/p2p?/<peer>".parse().unwrap();
+
+        let addr = ma("/ip4/1.2.3.4/tcp/80/p2p/{peer}");
+        let captures = pattern.captures(&addr).unwrap();
+        assert_eq!(captures.ip("host"), Some(Ipv4Addr::new(1, 2, 3, 4).into()));
+        assert_eq!(captures.domain("host"), None);
+        assert_eq!(captures.port("port"), Some(80));
+        assert_eq!(
+            captures.peer_id("peer"),
+            Some(PEER.parse::<PeerId>().unwrap())
+        );
+        assert_eq!(captures.get("port"), Some(&Protocol::Tcp(80)));
+        assert_eq!(captures.get("unknown"), None);
+
+        let addr = ma("/dns6/example.com/tcp/80");
+        let captures = pattern.captures(&addr).unwrap();
+        assert_eq!(captures.domain("host"), Some("example.com"));
+        assert_eq!(captures.peer_id("peer"), None);
+    }
+
+    #[test]
+    fn predefined() {
+        assert_matches(
+            patterns::tcp(),
+            &["/ip4/1.2.3.4/tcp/80", "/dns/example.com/tcp/80/p2p/{peer}"],
+            &["/ip4/1.2.3.4/udp/80", "/ip4/1.2.3.4/tcp/80/ws"],
+        );
+        assert_matches(
+            patterns::udp(),
+            &["/ip6/::1/udp/80"],
+            &["/ip6/::1/udp/80/quic-v1"],
+        );
+        assert_matches(
+            patterns::quic(),
+            &["/ip4/1.2.3.4/udp/443/quic"],
+            &["/ip4/1.2.3.4/udp/443/quic-v1"],
+        );
+        assert_matches(
+            patterns::quic_v1(),
+            &["/ip4/1.2.3.4/udp/443/quic-v1/p2p/{peer}"],
+            &["/ip4/1.2.3.4/udp/443/quic-v1/webtransport"],
+        );
+        assert_matches(
+            patterns::websocket(),
+            &[
+                "/ip4/1.2.3.4/tcp/80/ws",
+                "/ip4/1.2.3.4/tcp/80/x-parity-ws/%2Ffoo",
+            ],
+            &["/ip4/1.2.3.4/tcp/443/wss", "/ip4/1.2.3.4/tcp/443/tls/ws"],
+        );
+        assert_matches(
+            patterns::websocket_secure(),
+            &[
+                "/ip4/1.2.3.4/tcp/443/wss",
+                "/ip4/1.2.3.4/tcp/443/tls/ws/p2p/{peer}",
+                "/dns/example.com/tcp/443/tls/sni/example.com/ws",
+            ],
+            &["/ip4/1.2.3.4/tcp/80/ws", "/ip4/1.2.3.4/tcp/443/tls/wss"],
+        );
+        assert_matches(
+            patterns::webtransport(),
+            &[
+                "/ip4/1.2.3.4/udp/443/quic-v1/webtransport",
+                "/ip4/1.2.3.4/udp/443/quic-v1/webtransport/certhash/{certhash}/p2p/{peer}",
+            ],
+            &["/ip4/1.2.3.4/udp/443/quic/webtransport"],
+        );
+        assert_matches(
+            patterns::webrtc(),
+            &["/ip4/1.2.3.4/tcp/80/p2p/{relay}/p2p-circuit/webrtc/p2p/{peer}"],
+            &["/ip4/1.2.3.4/udp/443/webrtc-direct"],
+        );
+        assert_matches(
+            patterns::webrtc_direct(),
+            &["/ip4/1.2.3.4/udp/443/webrtc-direct/certhash/{certhash}"],
+            &["/ip4/1.2.3.4/tcp/443/webrtc-direct"],
+        );
+        assert_matches(
+            patterns::circuit(),
+            &[
+                "/ip4/1.2.3.4/tcp/80/p2p/{relay}/p2p-circuit",
+                "/ip4/1.2.3.4/tcp/80/p2p/{relay}/p2p-circuit/p2p/{peer}",
+            ],
+            &["/ip4/1.2.3.4/tcp/80/p2p-circuit/p2p/{peer}"],
+        );
+        assert_matches(
+            patterns::onion(),
+            &["/onion3/vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd:1234"],
+            &["/ip4/1.2.3.4/tcp/80"],
+        );
+
+        let addr = ma("/ip4/1.2.3.4/tcp/80/p2p/{relay}/p2p-circuit/p2p/{peer}");
+        let captures = patterns::circuit().captures(&addr).unwrap();
+        assert_eq!(
+            captures.peer_id("relay"),
+            Some(RELAY.parse::<PeerId>().unwrap())
+        );
+        assert_eq!(
+            captures.peer_id("peer"),
+            Some(PEER.parse::<PeerId>().unwrap())
+        );
+    }
+}
+
+#[test]
+fn filter() {
+    let ma = |s: &str| s.parse::<Multiaddr>().unwrap();
+
+    let mut filter = MultiaddrFilter::new(Action::Deny);
+    filter
+        .add_rule(&ma("/ip4/10.0.0.0/ipcidr/8"), Action::Accept)
+        .unwrap();
+    filter
+        .add_rule(&ma("/ip4/10.1.2.3/ipcidr/16"), Action::Deny)
+        .unwrap();
+    filter
+        .add_rule(&ma("/ip4/10.1.1.0/ipcidr/24"), Action::Accept)
+        .unwrap();
+    filter
+        .add_rule(&ma("/ip6/fd00::/ipcidr/8"), Action::Accept)
+        .unwrap();
+
+    let cases = [
+        ("/ip4/10.0.0.1/tcp/80", true),
+        ("/ip4/10.1.0.1/tcp/80", false),
+        ("/ip4/10.1.1.1/tcp/80", true),
+        ("/ip4/11.0.0.1/tcp/80", false),
+        ("/ip6/::ffff:10.0.0.1/tcp/80", true),
+        ("/ip6/::ffff:10.1.0.1/tcp/80", false),
+        ("/ip6/fd12::1/udp/443/quic-v1", true),
+        ("/ip6zone/eth0/ip6/fd12::1/tcp/80", true),
+        ("/ip6/fe80::1/tcp/80", false),
+        ("/dns/example.com/tcp/80", false),
+        ("/tcp/80/ip4/10.0.0.1", false),
+    ];
+    for (addr, allowed) in cases {
+        assert_eq!(filter.is_allowed(&ma(addr)), allowed, "{addr}");
+    }
+
+    // Host bits are cleared and rules for the same subnet are replaced.
+    assert_eq!(
+        filter.rules().nth(1),
+        Some((&*ma("/ip4/10.1.0.0/ipcidr/16"), Action::Deny))
+    );
+    filter
+        .add_rule(&ma("/ip6/::ffff:10.1.0.0/ipcidr/112"), Action::Accept)
+        .unwrap();
+    assert_eq!(filter.rules().count(), 4);
+    assert!(filter.is_allowed(&ma("/ip4/10.1.0.1")));
+    assert_eq!(
+        filter.remove_rule(&ma("/ip4/10.1.0.0/ipcidr/16")),
+        Some(Action::Accept)
+    );
+    assert_eq!(filter.remove_rule(&ma("/ip4/10.1.0.0/ipcidr/16")), None);
+
+    assert_eq!(
+        filter.ip_action("10.9.9.9".parse().unwrap()),
+        Action::Accept
+    );
+    filter.set_default_action(Action::Accept);
+    assert!(filter.is_allowed(&ma("/dns/example.com/tcp/80")));
+
+    // IPv6 supernets of `::ffff:0:0/96` do not cover IPv4.
+    let mut filter = MultiaddrFilter::new(Action::Accept);
+    filter
+        .add_rule(&ma("/ip6/::/ipcidr/0"), Action::Deny)
+        .unwrap();
+    filter
+        .add_rule(&ma("/ip6/::/ipcidr/64"), Action::Deny)
+        .unwrap();
+    assert!(filter.is_allowed(&ma("/ip4/1.2.3.4/tcp/80")));
+    assert!(filter.is_allowed(&ma("/ip6/::ffff:1.2.3.4/tcp/80")));
+    assert!(!filter.is_allowed(&ma("/ip6/2001:db8::1/tcp/80")));
+    assert!(!filter.is_allowed(&ma("/ip6/::1/tcp/80")));
+    filter
+        .add_rule(&ma("/ip6/::ffff:0.0.0.0/ipcidr/96"), Action::Deny)
+        .unwrap();
+    assert!(!filter.is_allowed(&ma("/ip4/1.2.3.4/tcp/80")));
+
+    for (subnet, err) in [
+        ("/ip4/10.0.0.0/ipcidr/33", FilterError::InvalidPrefix(33)),
+        ("/ip6/::/ipcidr/129", FilterError::InvalidPrefix(129)),
+        (
+            "/ip4/10.0.0.0",
+            FilterError::NotASubnet("/ip4/10.0.0.0".into()),
+        ),
+        (
+            "/ip4/10.0.0.0/ipcidr/8/tcp/80",
+            FilterError::NotASubnet("/ip4/10.0.0.0/ipcidr/8/tcp/80".into()),
+        ),
+    ] {
+        assert_eq!(filter.add_rule(&ma(subnet), Action::Deny), Err(err));
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn filter_serde() {
+    let json = r#"{"default":"deny","rules":{"/ip4/10.0.0.0/ipcidr/8":"accept","/ip6/fd00::/ipcidr/8":"accept"}}"#;
+    let filter: MultiaddrFilter = serde_json::from_str(json).unwrap();
+    assert_eq!(filter.default_action(), Action::Deny);
+    assert_eq!(filter.rules().count(), 2);
+    assert_eq!(serde_json::to_string(&filter).unwrap(), json);
+
+    let serialized = bincode::serialize(&filter).unwrap();
+    assert_eq!(
+        bincode::deserialize::<MultiaddrFilter>(&serialized).unwrap(),
+        filter
+    );
+
+    let empty: MultiaddrFilter = serde_json::from_str("{}").unwrap();
+    assert_eq!(empty, MultiaddrFilter::default());
+
+    assert!(serde_json::from_str::<MultiaddrFilter>(r#"{"default":"maybe"}"#).is_err());
+    assert!(
+        serde_json::from_str::<MultiaddrFilter>(r#"{"rules":{"/ip4/10.0.0.0":"deny"}}"#).is_err()
+    );
+    assert!(serde_json::from_str::<MultiaddrFilter>(r#"{"other":1}"#).is_err());
+}
+
+#[test]
+fn decapsulate() {
+    let ma = |s: &str| s.parse::<Multiaddr>().unwrap();
+    let relayed = ma("/ip4/1.2.3.4/tcp/80/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit/p2p/12D3KooWLRPJAA5o6Z6hDbVvG3WqCykmtgPufEAPvmRzjXXBfLeP");
+
+    assert_eq!(
+        ma("/ip4/1.2.3.4").encapsulate(&ma("/tcp/80")),
+        ma("/ip4/1.2.3.4/tcp/80")
+    );
+
+    assert_eq!(
+        relayed.decapsulate(&ma("/p2p-circuit")),
+        &*ma("/ip4/1.2.3.4/tcp/80/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC")
+    );
+    assert_eq!(relayed.decapsulate(&ma("/tcp/80")), &*ma("/ip4/1.2.3.4"));
+    assert_eq!(relayed.decapsulate(&ma("/tcp/81")), &*relayed);
+    assert_eq!(relayed.decapsulate(&Multiaddr::empty()), &*relayed);
+    assert_eq!(relayed.decapsulate(&relayed), &*Multiaddr::empty());
+    // `/tcp/4` is encoded as the bytes of the ip4 address `6.0.4.0`, which must not match.
+    assert_eq!(
+        ma("/ip4/6.0.4.6/tcp/5").decapsulate(&ma("/tcp/4")),
+        &*ma("/ip4/6.0.4.6/tcp/5")
+    );
+
+    let p2p = relayed.iter().nth(2).unwrap().code();
+    assert_eq!(
+        relayed.decapsulate_code(p2p),
+        &*ma("/ip4/1.2.3.4/tcp/80/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit")
+    );
+    assert_eq!(relayed.decapsulate_code(Protocol::Udp(0).code()), &*relayed);
+
+    let (before, p, after) = relayed.split_first(p2p).unwrap();
+    assert_eq!(before, &*ma("/ip4/1.2.3.4/tcp/80"));
+    assert_eq!(
+        p.to_string(),
+        "/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC"
+    );
+    assert_eq!(
+        after,
+        &*ma("/p2p-circuit/p2p/12D3KooWLRPJAA5o6Z6hDbVvG3WqCykmtgPufEAPvmRzjXXBfLeP")
+    );
+    let (before, p, after) = relayed.split_last(Protocol::P2pCircuit.code()).unwrap();
+    assert_eq!(
+        before,
+        &*ma("/ip4/1.2.3.4/tcp/80/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC")
+    );
+    assert_eq!(p, Protocol::P2pCircuit);
+    assert_eq!(
+        after,
+        &*ma("/p2p/12D3KooWLRPJAA5o6Z6hDbVvG3WqCykmtgPufEAPvmRzjXXBfLeP")
+    );
+    assert!(relayed.split_last(Protocol::Udp(0).code()).is_none());
+    assert_eq!(
+        relayed.split_last(ProtocolKind::P2pCircuit),
+        relayed.split_last(Protocol::P2pCircuit.code())
+    );
+    assert_eq!(relayed.decapsulate_code(ProtocolKind::Udp), &*relayed);
+    assert_eq!(u32::from(ProtocolKind::P2pCircuit), 290);
+    let ws = ma("/ip4/1.2.3.4/tcp/80/x-parity-ws/%2Ffoo");
+    assert!(ws.split_first(Protocol::Ws("/".into()).code()).is_none());
+    assert!(ws.split_first(Protocol::Ws("/foo".into()).code()).is_some());
+
+    let addr = ma("/ip4/1.2.3.4/tcp/80/ws");
+    assert_eq!(
+        addr.strip_prefix(&ma("/ip4/1.2.3.4")),
+        Some(&*ma("/tcp/80/ws"))
+    );
+    assert_eq!(addr.strip_prefix(&addr), Some(&*Multiaddr::empty()));
+    assert_eq!(addr.strip_prefix(&ma("/ip4/1.2.3.5")), None);
+    assert_eq!(
+        addr.strip_suffix(&ma("/tcp/80/ws")),
+        Some(&*ma("/ip4/1.2.3.4"))
+    );
+    assert_eq!(addr.strip_suffix(&Multiaddr::empty()), Some(&*addr));
+    assert_eq!(addr.strip_suffix(&ma("/udp/80/ws")), None);
+    assert_eq!(
+        ma("/ip4/1.2.3.4/ip4/1.6.4.6").strip_suffix(&ma("/tcp/1030")),
+        None
+    );
+}
+
+#[test]
+fn relayed_addr() {
+    let ma = |s: &str| s.parse::<Multiaddr>().unwrap();
+    let relay: PeerId = "QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC"
+        .parse()
+        .unwrap();
+    let second: PeerId = "QmNnooDu7bfjPFoTZYxMNLWUQJyrVwtbZg5gBMjTezGAJN"
+        .parse()
+        .unwrap();
+    let destination: PeerId = "12D3KooWLRPJAA5o6Z6hDbVvG3WqCykmtgPufEAPvmRzjXXBfLeP"
+        .parse()
+        .unwrap();
+
+    let cases = [
+        (
+            "/ip4/1.2.3.4/tcp/80/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit",
+            "/ip4/1.2.3.4/tcp/80",
+            vec![relay],
+            None,
+        ),
+        (
+            "/ip4/1.2.3.4/tcp/80/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit/p2p/12D3KooWLRPJAA5o6Z6hDbVvG3WqCykmtgPufEAPvmRzjXXBfLeP",
+            "/ip4/1.2.3.4/tcp/80",
+            vec![relay],
+            Some(destination),
+        ),
+        (
+            "/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit/p2p/QmNnooDu7bfjPFoTZYxMNLWUQJyrVwtbZg5gBMjTezGAJN/p2p-circuit/p2p/12D3KooWLRPJAA5o6Z6hDbVvG3WqCykmtgPufEAPvmRzjXXBfLeP",
+            "",
+            vec![relay, second],
+            Some(destination),
+        ),
+    ];
+    for (addr, relay_addr, relays, dest) in cases {
+        let addr = ma(addr);
+        let relayed = RelayedAddr::try_from(&addr).unwrap();
+        assert_eq!(relayed.relay_addr(), &*ma(relay_addr), "{addr}");
+        assert_eq!(relayed.relay(), &relays[0], "{addr}");
+        assert_eq!(relayed.relays(), &relays[..], "{addr}");
+        assert_eq!(relayed.destination(), dest.as_ref(), "{addr}");
+        assert_eq!(relayed.to_string(), addr.to_string());
+        assert_eq!(Multiaddr::from(relayed.clone()), addr);
+
+        let mut builder = RelayedAddr::builder(ma(relay_addr).with(Protocol::P2p(relays[0])));
+        for relay in &relays[1..] {
+            builder = builder.via(*relay);
+        }
+        if let Some(dest) = dest {
+            builder = builder.destination(dest);
+        }
+        assert_eq!(builder.build().unwrap(), relayed);
+        assert!(relayed.upgrades().is_empty());
+    }
+
+    // A `/webrtc` upgrade may follow the last circuit.
+    for addr in [
+        "/ip4/1.2.3.4/tcp/80/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit/webrtc/p2p/12D3KooWLRPJAA5o6Z6hDbVvG3WqCykmtgPufEAPvmRzjXXBfLeP",
+        "/ip4/1.2.3.4/tcp/80/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit/webrtc",
+    ] {
+        let addr = ma(addr);
+        let relayed = RelayedAddr::try_from(&addr).unwrap();
+        assert_eq!(relayed.relay_addr(), &*ma("/ip4/1.2.3.4/tcp/80"));
+        assert_eq!(relayed.relays(), &[relay]);
+        assert_eq!(relayed.upgrades(), &*ma("/webrtc"));
+        assert_eq!(Multiaddr::from(relayed.clone()), addr);
+
+        let mut builder = RelayedAddr::builder(ma("/ip4/1.2.3.4/tcp/80").with(Protocol::P2p(relay)))
+            .upgrade(Protocol::WebRTC);
+        if let Some(dest) = relayed.destination() {
+            builder = builder.destination(*dest);
+        }
+        assert_eq!(builder.build().unwrap(), relayed);
+    }
+    assert_eq!(
+        RelayedAddr::builder(ma("/ip4/1.2.3.4/tcp/80").with(Protocol::P2p(relay)))
+            .upgrade(Protocol::Tcp(80))
+            .build(),
+        Err(RelayedAddrErr::UnexpectedProtocol {
+            index: 4,
+            tag: "tcp"
+        })
+    );
+
+    let failures = [
+        ("/ip4/1.2.3.4/tcp/80", RelayedAddrErr::NotRelayed),
+        (
+            "/ip4/1.2.3.4/tcp/80/p2p-circuit/p2p/12D3KooWLRPJAA5o6Z6hDbVvG3WqCykmtgPufEAPvmRzjXXBfLeP",
+            RelayedAddrErr::MissingRelay { index: 2 },
+        ),
+        ("/p2p-circuit", RelayedAddrErr::MissingRelay { index: 0 }),
+        (
+            "/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit/p2p-circuit",
+            RelayedAddrErr::MissingRelay { index: 2 },
+        ),
+        (
+            "/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit/ip4/1.2.3.4",
+            RelayedAddrErr::UnexpectedProtocol { index: 2, tag: "ip4" },
+        ),
+        (
+            "/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit/webrtc/p2p/QmNnooDu7bfjPFoTZYxMNLWUQJyrVwtbZg5gBMjTezGAJN/p2p-circuit",
+            RelayedAddrErr::UnexpectedProtocol { index: 2, tag: "webrtc" },
+        ),
+        (
+            "/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit/p2p/12D3KooWLRPJAA5o6Z6hDbVvG3WqCykmtgPufEAPvmRzjXXBfLeP/webrtc",
+            RelayedAddrErr::UnexpectedProtocol { index: 3, tag: "webrtc" },
+        ),
+        (
+            "/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit/p2p/12D3KooWLRPJAA5o6Z6hDbVvG3WqCykmtgPufEAPvmRzjXXBfLeP/tcp/80",
+            RelayedAddrErr::UnexpectedProtocol { index: 3, tag: "tcp" },
+        ),
+    ];
+    for (addr, err) in failures {
+        assert_eq!(RelayedAddr::try_from(&ma(addr)), Err(err), "{addr}");
+    }
+
+    assert_eq!(
+        RelayedAddr::builder(ma("/ip4/1.2.3.4/tcp/80")).build(),
+        Err(RelayedAddrErr::MissingRelay { index: 2 })
+    );
+    assert_eq!(
+        RelayedAddr::builder(ma(
+            "/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit/p2p/QmNnooDu7bfjPFoTZYxMNLWUQJyrVwtbZg5gBMjTezGAJN"
+        ))
+        .build(),
+        Err(RelayedAddrErr::UnexpectedProtocol {
+            index: 1,
+            tag: "p2p-circuit"
+        })
+    );
+}
+
+#[test]
+fn resolve() {
+    let ma = |s: &str| s.parse::<Multiaddr>().unwrap();
+    let mut resolver = MockResolver::new();
+    resolver.add_ip("example.com", "1.2.3.4".parse().unwrap());
+    resolver.add_ip("example.com", "::1".parse().unwrap());
+    resolver.add_ip("v6.example.com", "::2".parse().unwrap());
+
+    let cases = [
+        (
+            "/dns/example.com/tcp/80/ws",
+            vec!["/ip4/1.2.3.4/tcp/80/ws", "/ip6/::1/tcp/80/ws"],
+        ),
+        ("/dns4/EXAMPLE.com./tcp/80", vec!["/ip4/1.2.3.4/tcp/80"]),
+        ("/dns6/example.com/tcp/80", vec!["/ip6/::1/tcp/80"]),
+        ("/dns4/v6.example.com/tcp/80", vec![]),
+        ("/ip4/5.6.7.8/tcp/80", vec!["/ip4/5.6.7.8/tcp/80"]),
+        (
+            "/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit/dns4/example.com/tcp/80",
+            vec!["/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit/ip4/1.2.3.4/tcp/80"],
+        ),
+    ];
+    for (addr, expected) in cases {
+        let expected = expected.into_iter().map(ma).collect::<Vec<_>>();
+        assert_eq!(ma(addr).resolve(&resolver).unwrap(), expected, "{addr}");
+    }
+
+    let err = ma("/dns/unknown.com/tcp/80")
+        .resolve(&resolver)
+        .unwrap_err();
+    assert_eq!(err.name(), "unknown.com");
+    assert_eq!(err.io_error().kind(), std::io::ErrorKind::NotFound);
+}
+
+#[test]
+fn resolve_dnsaddr() {
+    let ma = |s: &str| s.parse::<Multiaddr>().unwrap();
+    let mut resolver = MockResolver::new();
+    resolver.add_ip("node.example.com", "1.2.3.4".parse().unwrap());
+    resolver.add_txt(
+        "_dnsaddr.bootstrap.example.com",
+        "dnsaddr=/dnsaddr/a.example.com",
+    );
+    resolver.add_txt(
+        "_dnsaddr.bootstrap.example.com",
+        "dnsaddr=/ip4/5.6.7.8/tcp/4001/p2p/QmNnooDu7bfjPFoTZYxMNLWUQJyrVwtbZg5gBMjTezGAJN",
+    );
+    resolver.add_txt("_dnsaddr.bootstrap.example.com", "v=spf1 -all");
+    resolver.add_txt("_dnsaddr.bootstrap.example.com", "dnsaddr=/invalid");
+    resolver.add_txt(
+        "_dnsaddr.a.example.com",
+        "dnsaddr=/dns4/node.example.com/tcp/4001/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC",
+    );
+    // A cycle back to the bootstrap name.
+    resolver.add_txt(
+        "_dnsaddr.a.example.com",
+        "dnsaddr=/dnsaddr/bootstrap.example.com",
+    );
+
+    assert_eq!(
+        ma("/dnsaddr/bootstrap.example.com")
+            .resolve(&resolver)
+            .unwrap(),
+        vec![
+            ma("/ip4/1.2.3.4/tcp/4001/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC"),
+            ma("/ip4/5.6.7.8/tcp/4001/p2p/QmNnooDu7bfjPFoTZYxMNLWUQJyrVwtbZg5gBMjTezGAJN"),
+        ]
+    );
+    assert_eq!(
+        ma("/dnsaddr/bootstrap.example.com/p2p/QmNnooDu7bfjPFoTZYxMNLWUQJyrVwtbZg5gBMjTezGAJN")
+            .resolve(&resolver)
+            .unwrap(),
+        vec![ma(
+            "/ip4/5.6.7.8/tcp/4001/p2p/QmNnooDu7bfjPFoTZYxMNLWUQJyrVwtbZg5gBMjTezGAJN"
+        )]
+    );
+
+    // Failed lookups of records drop these records, whereas a failed lookup of the
+    // original address fails.
+    let mut resolver = MockResolver::new();
+    resolver.add_ip("node.example.com", "1.2.3.4".parse().unwrap());
+    resolver.add_txt(
+        "_dnsaddr.bootstrap.example.com",
+        "dnsaddr=/dns4/unknown.example.com/tcp/4001",
+    );
+    resolver.add_txt(
+        "_dnsaddr.bootstrap.example.com",
+        "dnsaddr=/dnsaddr/unknown.example.com",
+    );
+    resolver.add_txt(
+        "_dnsaddr.bootstrap.example.com",
+        "dnsaddr=/dns4/node.example.com/tcp/4001",
+    );
+    assert_eq!(
+        ma("/dnsaddr/bootstrap.example.com")
+            .resolve(&resolver)
+            .unwrap(),
+        vec![ma("/ip4/1.2.3.4/tcp/4001")]
+    );
+    let err = ma("/dnsaddr/unknown.example.com")
+        .resolve(&resolver)
+        .unwrap_err();
+    assert_eq!(err.name(), "_dnsaddr.unknown.example.com");
+
+    // Each level points to the next one, deeper than the limit.
+    let mut resolver = MockResolver::new();
+    for i in 0..MAX_DNSADDR_DEPTH + 1 {
+        resolver.add_txt(
+            &format!("_dnsaddr.{i}.example.com"),
+            format!("dnsaddr=/dnsaddr/{}.example.com", i + 1),
+        );
+        resolver.add_txt(
+            &format!("_dnsaddr.{i}.example.com"),
+            format!("dnsaddr=/ip4/10.0.0.{i}/tcp/4001"),
+        );
+    }
+    let resolved = ma("/dnsaddr/0.example.com").resolve(&resolver).unwrap();
+    assert_eq!(resolved.len(), MAX_DNSADDR_DEPTH);
+    assert_eq!(resolved[0], ma("/ip4/10.0.0.7/tcp/4001"));
+}
+
+#[test]
+fn resolve_dnsaddr_fan_out() {
+    use std::{cell::Cell, io, net::IpAddr};
+
+    /// Counts the lookups passed on to a [`MockResolver`].
+    struct Counting(MockResolver, Cell<usize>);
+
+    impl Resolver for Counting {
+        fn lookup_ip(&self, name: &str) -> io::Result<Vec<IpAddr>> {
+            self.1.set(self.1.get() + 1);
+            Resolver::lookup_ip(&self.0, name)
+        }
+
+        fn lookup_txt(&self, name: &str) -> io::Result<Vec<String>> {
+            self.1.set(self.1.get() + 1);
+            Resolver::lookup_txt(&self.0, name)
+        }
+    }
+
+    let ma = |s: &str| s.parse::<Multiaddr>().unwrap();
+
+    // Each name refers to 4 names one level deeper, and to 4 addresses, which would take
+    // 4^8 lookups without a limit.
+    let mut resolver = MockResolver::new();
+    for depth in 0..=MAX_DNSADDR_DEPTH {
+        for i in 0..4 {
+            let name = format!("_dnsaddr.{depth}-{i}.example.com");
+            for j in 0..4 {
+                resolver.add_txt(
+                    &name,
+                    format!("dnsaddr=/dnsaddr/{}-{j}.example.com", depth + 1),
+                );
+                resolver.add_txt(&name, format!("dnsaddr=/ip4/10.{depth}.{i}.{j}/tcp/4001"));
+            }
+        }
+    }
+    let resolver = Counting(resolver, Cell::new(0));
+    let resolved = ma("/dnsaddr/0-0.example.com").resolve(&resolver).unwrap();
+    assert!(resolver.1.get() <= MAX_LOOKUPS);
+    assert!(!resolved.is_empty() && resolved.len() <= MAX_RESOLVED_ADDRS);
+
+    // Many addresses per record are truncated.
+    let mut resolver = MockResolver::new();
+    for i in 0..MAX_RESOLVED_ADDRS + 10 {
+        resolver.add_ip(
+            "many.example.com",
+            Ipv4Addr::from(0x0a00_0000 + i as u32).into(),
+        );
+    }
+    let resolved = ma("/dns4/many.example.com/tcp/80")
+        .resolve(&resolver)
+        .unwrap();
+    assert_eq!(resolved.len(), MAX_RESOLVED_ADDRS);
+    assert_eq!(resolved[0], ma("/ip4/10.0.0.0/tcp/80"));
+
+    // Cycles are detected regardless of case and trailing dots, before looking them up.
+    let mut resolver = MockResolver::new();
+    resolver.add_txt("_dnsaddr.a.example.com", "dnsaddr=/dnsaddr/A.Example.com.");
+    resolver.add_txt("_dnsaddr.a.example.com", "dnsaddr=/ip4/1.2.3.4/tcp/4001");
+    let resolver = Counting(resolver, Cell::new(0));
+    assert_eq!(
+        ma("/dnsaddr/a.example.com").resolve(&resolver).unwrap(),
+        vec![ma("/ip4/1.2.3.4/tcp/4001")]
+    );
+    assert_eq!(resolver.1.get(), 1);
+}
+
+#[test]
+fn resolve_async() {
+    use std::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+    };
+
+    // The mock resolver completes immediately, so polling once suffices.
+    fn poll_once<F: Future>(future: F) -> F::Output {
+        const VTABLE: RawWakerVTable = RawWakerVTable::new(
+            |_| RawWaker::new(std::ptr::null(), &VTABLE),
+            |_| {},
+            |_| {},
+            |_| {},
+        );
+        let waker = unsafe { Waker::from_raw(RawWaker::new(std::ptr::null(), &VTABLE)) };
+        match pin!(future).poll(&mut Context::from_waker(&waker)) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("mock resolution is never pending"),
+        }
+    }
+
+    let mut resolver = MockResolver::new();
+    resolver.add_ip("example.com", "1.2.3.4".parse().unwrap());
+    resolver.add_txt(
+        "_dnsaddr.example.com",
+        "dnsaddr=/dns/example.com/udp/443/quic-v1",
+    );
+
+    let addr: Multiaddr = "/dnsaddr/example.com".parse().unwrap();
+    assert_eq!(
+        poll_once(addr.resolve_async(&resolver)).unwrap(),
+        addr.resolve(&resolver).unwrap()
+    );
+    assert_eq!(
+        poll_once(addr.resolve_async(&resolver)).unwrap(),
+        vec!["/ip4/1.2.3.4/udp/443/quic-v1".parse::<Multiaddr>().unwrap()]
+    );
+}
+
+#[test]
+fn dnsaddr_records() {
+    let ma = |s: &str| s.parse::<Multiaddr>().unwrap();
+    let peer: PeerId = "QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC"
+        .parse()
+        .unwrap();
+
+    let parsed = dnsaddr::parse_records([
+        "dnsaddr=/ip4/1.2.3.4/tcp/4001/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC",
+        "dnsaddr=/dnsaddr/other.example.com",
+        "dnsaddr=/ip6/::1/udp/4001/quic-v1/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit",
+        "v=spf1 -all",
+        "dnsaddr=ip4/1.2.3.4",
+        "dnsaddr=/ip4/1.2.3.4/tcp/notaport",
+    ]);
+    assert_eq!(parsed.len(), 6);
+    assert!(matches!(parsed[3], Err(dnsaddr::DnsaddrErr::MissingPrefix)));
+    assert!(matches!(
+        parsed[4],
+        Err(dnsaddr::DnsaddrErr::InvalidMultiaddr(_))
+    ));
+    assert!(matches!(
+        parsed[5],
+        Err(dnsaddr::DnsaddrErr::InvalidMultiaddr(_))
+    ));
+
+    let addrs = parsed.into_iter().flatten().collect::<Vec<_>>();
+    assert_eq!(addrs.len(), 3);
+    assert_eq!(
+        dnsaddr::for_peer(addrs.clone(), &peer),
+        vec![ma(
+            "/ip4/1.2.3.4/tcp/4001/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC"
+        )]
+    );
+
+    let records = dnsaddr::to_records(&addrs);
+    assert_eq!(
+        records.into_iter().map(|r| r.unwrap()).collect::<Vec<_>>(),
+        vec![
+            "dnsaddr=/ip4/1.2.3.4/tcp/4001/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC",
+            "dnsaddr=/dnsaddr/other.example.com",
+            "dnsaddr=/ip6/::1/udp/4001/quic-v1/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit",
+        ]
+    );
+
+    // "dnsaddr=/dns/" is 13 bytes, so a 242 byte name is just within the limit.
+    let longest = ma(&format!("/dns/{}", "a".repeat(242)));
+    assert_eq!(
+        dnsaddr::to_record(&longest).unwrap().len(),
+        dnsaddr::MAX_RECORD_LEN
+    );
+    let too_long = ma(&format!("/dns/{}", "a".repeat(243)));
+    assert!(matches!(
+        dnsaddr::to_record(&too_long),
+        Err(dnsaddr::DnsaddrErr::TooLong(256))
+    ));
+    assert!(matches!(
+        dnsaddr::to_record(&Multiaddr::empty()),
+        Err(dnsaddr::DnsaddrErr::NotRepresentable)
+    ));
+    // A dns name containing `/` cannot be parsed back.
+    let slash = Multiaddr::empty().with(Protocol::Dns("a/b".into()));
+    assert!(matches!(
+        dnsaddr::to_record(&slash),
+        Err(dnsaddr::DnsaddrErr::NotRepresentable)
+    ));
+}
+
+#[test]
+fn normalize() {
+    let ma = |s: &str| s.parse::<Multiaddr>().unwrap();
+
+    let cases = [
+        (
+            "/ip4/1.2.3.4/tcp/443/https",
+            "/ip4/1.2.3.4/tcp/443/tls/http",
+        ),
+        ("/ip4/1.2.3.4/tcp/443/wss", "/ip4/1.2.3.4/tcp/443/tls/ws"),
+        (
+            "/ip4/1.2.3.4/tcp/443/x-parity-wss/%2Ffoo",
+            "/ip4/1.2.3.4/tcp/443/tls/ws/http-path/foo",
+        ),
+        (
+            "/ip4/1.2.3.4/tcp/80/x-parity-ws/%2Ffoo%2Fbar",
+            "/ip4/1.2.3.4/tcp/80/ws/http-path/foo%2Fbar",
+        ),
+        (
+            "/ip4/1.2.3.4/tcp/80/ws/http-path/foo",
+            "/ip4/1.2.3.4/tcp/80/ws/http-path/foo",
+        ),
+        (
+            "/ipfs/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC",
+            "/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC",
+        ),
+        ("/ip6/::ffff:1.2.3.4/udp/1", "/ip4/1.2.3.4/udp/1"),
+        ("/ip6/::1/udp/1", "/ip6/::1/udp/1"),
+        ("/ip6/::ffff:1.2.3.0/ipcidr/120", "/ip4/1.2.3.0/ipcidr/24"),
+        ("/ip6/::ffff:0.0.0.0/ipcidr/96", "/ip4/0.0.0.0/ipcidr/0"),
+        (
+            "/ip6/::ffff:0.0.0.0/ipcidr/64",
+            "/ip6/::ffff:0.0.0.0/ipcidr/64",
+        ),
+        (
+            "/ip6zone/eth0/ip6/::ffff:1.2.3.4",
+            "/ip6zone/eth0/ip6/::ffff:1.2.3.4",
+        ),
+        (
+            "/ip6/fe80::1/ip6zone/eth0/tcp/1",
+            "/ip6zone/eth0/ip6/fe80::1/tcp/1",
+        ),
+        ("/dns/Example.COM./tcp/80", "/dns/example.com/tcp/80"),
+        ("/dns4/EXAMPLE.com/tcp/80", "/dns4/example.com/tcp/80"),
+        ("/dns6/example.com../tcp/80", "/dns6/example.com/tcp/80"),
+        (
+            "/dnsaddr/Bootstrap.libp2p.io.",
+            "/dnsaddr/bootstrap.libp2p.io",
+        ),
+        (
+            "/dns/example.com/tcp/443/tls/sni/Example.com./ws",
+            "/dns/example.com/tcp/443/tls/sni/example.com/ws",
+        ),
+        ("/ip4/1.2.3.4/tcp/80/ws", "/ip4/1.2.3.4/tcp/80/ws"),
+    ];
+    for (addr, normalized) in cases {
+        let addr = ma(addr);
+        let normalized = ma(normalized);
+        assert_eq!(addr.normalize(), normalized, "{addr}");
+        assert_eq!(normalized.normalize(), normalized, "{normalized}");
+        assert!(addr.is_equivalent(&normalized), "{addr}");
+        assert!(normalized.is_equivalent(&addr), "{addr}");
+    }
+
+    assert!(!ma("/ip4/1.2.3.4/tcp/80").is_equivalent(&ma("/ip4/1.2.3.4/udp/80")));
+    assert!(!ma("/dns4/example.com").is_equivalent(&ma("/dns/example.com")));
+    assert!(!ma("/ip4/1.2.3.4/tcp/443/wss").is_equivalent(&ma("/ip4/1.2.3.4/tcp/443/ws")));
+
+    fn prop(a: Ma) -> bool {
+        let normalized = a.0.normalize();
+        normalized.normalize() == normalized && a.0.is_equivalent(&normalized)
+    }
+    QuickCheck::new().quickcheck(prop as fn(Ma) -> bool)
+}
+
+#[test]
+fn validate() {
+    let valid = [
+        "/ip4/1.2.3.4/tcp/80",
+        "/ip6zone/eth0/ip6/fe80::1/udp/443/quic-v1",
+        "/dns/example.com/tcp/443/tls/sni/example.com/ws/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC",
+        "/dns4/example.com/tcp/443/tls/http/http-path/foo",
+        "/ip4/1.2.3.4/tcp/443/wss",
+        "/ip4/1.2.3.4/tcp/443/x-parity-ws/%2Ffoo",
+        "/ip4/1.2.3.4/udp/443/quic-v1/webtransport/certhash/uEiDDq4_xNyDorZBH3TlGazyJdOWSwvo4PUo5YHFMrvDE8g/certhash/uEiDDq4_xNyDorZBH3TlGazyJdOWSwvo4PUo5YHFMrvDE8g",
+        "/ip4/1.2.3.4/udp/443/webrtc-direct/certhash/uEiDDq4_xNyDorZBH3TlGazyJdOWSwvo4PUo5YHFMrvDE8g",
+        "/ip4/1.2.3.4/tcp/80/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p-circuit/webrtc/p2p/12D3KooWLRPJAA5o6Z6hDbVvG3WqCykmtgPufEAPvmRzjXXBfLeP",
+        "/p2p-circuit",
+        "/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC",
+        "/ip4/10.0.0.0/ipcidr/8",
+        "/ip6/fd00::/ipcidr/8",
+        "/memory/1234",
+        "/onion3/vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd:1234",
+        "/dnsaddr/bootstrap.libp2p.io",
+        "",
+    ];
+    for addr in valid {
+        let addr: Multiaddr = addr.parse().unwrap();
+        assert_eq!(addr.validate(), Ok(()), "{addr}");
+        assert_eq!(Multiaddr::from_str_strict(&addr.to_string()).unwrap(), addr);
+    }
+
+    let host = Rule::Follows {
+        after: &["p2p-circuit"],
+        at_start: true,
+    };
+    let port = Rule::Follows {
+        after: &["ip4", "ip6", "dns", "dns4", "dns6"],
+        at_start: false,
+    };
+    let invalid = [
+        (
+            "/tcp/80/ip4/1.2.3.4",
+            vec![(0, "tcp", port.clone()), (1, "ip4", host.clone())],
+        ),
+        (
+            "/ip4/1.2.3.4/ipcidr/200",
+            vec![(1, "ipcidr", Rule::PrefixTooLong { max: 32 })],
+        ),
+        (
+            "/ip6/::/ipcidr/129",
+            vec![(1, "ipcidr", Rule::PrefixTooLong { max: 128 })],
+        ),
+        (
+            "/ip4/1.2.3.4/udp/443/quic-v1/certhash/uEiDDq4_xNyDorZBH3TlGazyJdOWSwvo4PUo5YHFMrvDE8g",
+            vec![(
+                3,
+                "certhash",
+                Rule::Follows {
+                    after: &["webtransport", "webrtc-direct", "certhash"],
+                    at_start: false,
+                },
+            )],
+        ),
+        (
+            "/ip4/1.2.3.4/tcp/443/sni/example.com",
+            vec![(
+                2,
+                "sni",
+                Rule::Follows {
+                    after: &["tls"],
+                    at_start: false,
+                },
+            )],
+        ),
+        (
+            "/ip6zone/eth0/ip4/1.2.3.4",
+            vec![
+                (0, "ip6zone", Rule::Precedes(&["ip6"])),
+                (1, "ip4", host),
+            ],
+        ),
+        (
+            "/ip4/1.2.3.4/tcp/80/p2p-circuit",
+            vec![(
+                2,
+                "p2p-circuit",
+                Rule::Follows {
+                    after: &["p2p"],
+                    at_start: true,
+                },
+            )],
+        ),
+        (
+            "/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC",
+            vec![(1, "p2p", Rule::NotAfter(&["p2p"]))],
+        ),
+        ("/ip4/1.2.3.4/udp/1/quic-v1/tcp/1", vec![(3, "tcp", port)]),
+        (
+            "/ip4/1.2.3.4/tcp/1/tls/sni//ws",
+            vec![(3, "sni", Rule::EmptyValue)],
+        ),
+    ];
+    for (addr, expected) in invalid {
+        let expected = expected
+            .into_iter()
+            .map(|(index, tag, rule)| Violation { index, tag, rule })
+            .collect::<Vec<_>>();
+        let parsed = addr.parse::<Multiaddr>().unwrap();
+        assert_eq!(parsed.validate(), Err(expected), "{addr}");
+        assert!(matches!(
+            Multiaddr::from_str_strict(addr),
+            Err(StrictParseError::Invalid(_))
+        ));
+    }
+
+    assert_eq!(
+        Violation {
+            index: 2,
+            tag: "sni",
+            rule: Rule::Follows {
+                after: &["tls"],
+                at_start: false
+            }
+        }
+        .to_string(),
+        "`sni` at component 2 must follow `tls`"
+    );
+    assert!(matches!(
+        Multiaddr::from_str_strict("/ip4/1.2.3.4/tcp/notaport"),
+        Err(StrictParseError::Parse(_))
+    ));
+}
+
+#[test]
+fn positional_errors() {
+    let parse = |s: &str| s.parse::<Multiaddr>().unwrap_err();
+
+    let error = parse("/ip4/1.2.3.4/tpc/80");
+    assert!(matches!(
+        &error,
+        Error::InvalidComponentString { index: 1, offset: 13, protocol: None, value: Some(v), .. }
+            if v == "tpc"
+    ));
+    assert_eq!(error.suggestion(), Some("tcp"));
+
+    let error = parse("/ip4/1.2.3.4/tcp/80x");
+    assert!(matches!(
+        &error,
+        Error::InvalidComponentString { index: 1, offset: 17, protocol: Some("tcp"), value: Some(v), .. }
+            if v == "80x"
+    ));
+    assert!(matches!(error.inner(), Error::InvalidPort(_)));
+    assert_eq!(
+        error.to_string(),
+        "`80x` of `/tcp` at component 1, offset 17: invalid port: invalid digit found in string"
+    );
+
+    let error = parse("/ip4/1.2.3.4/udp/1/quic-v1/p2p");
+    assert_eq!((error.index(), error.offset()), (Some(3), Some(27)));
+    assert!(matches!(error.inner(), Error::MissingValue));
+
+    // Offsets count characters, not bytes.
+    let error = parse("/dns/bücher.example/tcp/x");
+    assert_eq!(error.offset(), Some(24));
+
+    for (s, check) in [
+        (
+            "/ip4/1.2.3",
+            (|e| matches!(e, Error::InvalidIpAddr(_))) as fn(&Error) -> bool,
+        ),
+        ("/ip6/::1::", |e| matches!(e, Error::InvalidIpAddr(_))),
+        ("/udp/65536", |e| matches!(e, Error::InvalidPort(_))),
+        ("/p2p/QmInvalid", |e| matches!(e, Error::InvalidPeerId(_))),
+        ("/certhash/uAAAA", |e| {
+            matches!(e, Error::InvalidMultihash(_))
+        }),
+        ("/dns", |e| matches!(e, Error::MissingValue)),
+    ] {
+        let error = parse(s);
+        assert_eq!(error.index(), Some(0), "{s}");
+        assert!(check(error.inner()), "{s}: {error}");
+    }
+
+    let suggest = |s: &str| parse(s).suggestion();
+    assert_eq!(suggest("/quicv1"), Some("quic-v1"));
+    assert_eq!(suggest("/TCP/80"), Some("tcp"));
+    assert_eq!(suggest("/webtransprt"), Some("webtransport"));
+    assert_eq!(suggest("/foobar"), None);
+
+    // Binary errors carry the byte offset, the protocol and the undecodable value.
+    let mut bytes = "/ip4/1.2.3.4".parse::<Multiaddr>().unwrap().to_vec();
+    bytes.extend([0x06, 0x01]);
+    let error = Multiaddr::try_from(bytes).unwrap_err();
+    assert!(matches!(
+        &error,
+        Error::InvalidComponentBytes { index: 1, offset: 5, protocol: Some("tcp"), value, .. }
+            if value == &[0x01]
+    ));
+    assert!(matches!(error.inner(), Error::DataLessThanLen));
+    assert_eq!(
+        error.to_string(),
+        "`/tcp` at component 1, byte offset 5: we have less data than indicated by length"
+    );
+}
+
+#[test]
+fn custom_writer() {
+    struct Counter(usize);
+
+    impl multiaddr::Writer for Counter {
+        fn write_all(&mut self, buf: &[u8]) -> multiaddr::Result<()> {
+            self.0 += buf.len();
+            Ok(())
+        }
+    }
+
+    let addr: Multiaddr = "/ip4/1.2.3.4/tcp/80/x-parity-ws/%2Fchat".parse().unwrap();
+    let mut counter = Counter(0);
+    for p in addr.iter() {
+        p.write_bytes(&mut counter).unwrap();
+    }
+    assert_eq!(counter.0, addr.len());
+}
+
+#[test]
+fn peer_id_validation() {
+    use multiaddr::ParsePeerIdError;
+
+    let peer: PeerId = "12D3KooWLRPJAA5o6Z6hDbVvG3WqCykmtgPufEAPvmRzjXXBfLeP"
+        .parse()
+        .unwrap();
+    assert_eq!(PeerId::from_bytes(&peer.to_bytes()).unwrap(), peer);
+    assert_eq!(
+        format!("{peer:?}"),
+        "PeerId(\"12D3KooWLRPJAA5o6Z6hDbVvG3WqCykmtgPufEAPvmRzjXXBfLeP\")"
+    );
+
+    assert!(matches!(
+        "0OIl".parse::<PeerId>(),
+        Err(ParsePeerIdError::InvalidBase58)
+    ));
+    assert!(matches!(
+        PeerId::from_bytes(&[0x12, 0x20]),
+        Err(ParsePeerIdError::InvalidMultihash(_))
+    ));
+    let sha1 = Multihash::<64>::wrap(0x11, &[0; 20]).unwrap();
+    assert!(matches!(
+        PeerId::from_bytes(&sha1.to_bytes()),
+        Err(ParsePeerIdError::UnsupportedCode(0x11))
+    ));
+    let long_identity = Multihash::<64>::wrap(0, &[0; 43]).unwrap();
+    assert_eq!(PeerId::from_multihash(long_identity), Err(long_identity));
+
+    let bytes = [&[0xa5, 0x03, 0x04, 0x12, 0x20][..], &[0; 32]].concat();
+    assert!(matches!(
+        Multiaddr::try_from(bytes).unwrap_err().inner(),
+        Error::InvalidPeerId(_)
+    ));
+}
+
+#[cfg(feature = "libp2p-identity")]
+#[test]
+fn libp2p_identity_peer_id() {
+    let peer: libp2p_identity::PeerId = "12D3KooWLRPJAA5o6Z6hDbVvG3WqCykmtgPufEAPvmRzjXXBfLeP"
+        .parse()
+        .unwrap();
+    let converted = PeerId::from(peer);
+    assert_eq!(converted.to_string(), peer.to_string());
+    assert_eq!(libp2p_identity::PeerId::from(converted), peer);
+
+    let addr: Multiaddr = "/ip4/1.2.3.4/tcp/80".parse().unwrap();
+    let addr = addr.with_p2p(peer).unwrap();
+    assert_eq!(addr.iter().last(), Some(Protocol::P2p(converted)));
+}
+
+#[test]
+fn length_prefixed() {
+    let addrs: Vec<Multiaddr> = vec![
+        "/ip4/1.2.3.4/tcp/80".parse().unwrap(),
+        "/dns/example.com/tcp/443/wss".parse().unwrap(),
+        Multiaddr::empty(),
+    ];
+    let mut buf = Vec::new();
+    for addr in &addrs {
+        addr.write_length_prefixed(&mut buf).unwrap();
+    }
+
+    let mut reader = &buf[..];
+    for addr in &addrs {
+        let read = Multiaddr::read_length_prefixed(&mut reader, DEFAULT_MAX_FRAME_LEN).unwrap();
+        assert_eq!(&read, addr);
+    }
+    assert!(matches!(
+        Multiaddr::read_length_prefixed(&mut reader, DEFAULT_MAX_FRAME_LEN),
+        Err(FramingError::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof
+    ));
+
+    assert!(matches!(
+        Multiaddr::read_length_prefixed(&mut &buf[..], 4),
+        Err(FramingError::TooLong { len: 8, max_len: 4 })
+    ));
+    assert!(matches!(
+        Multiaddr::read_length_prefixed(&mut &[0x02, 0x04, 0x01][..], 4),
+        Err(FramingError::InvalidMultiaddr(_))
+    ));
+}
+
+/// Checks the built-in protocols against `protocols.csv`, which must be an unmodified copy of
+/// https://github.com/multiformats/multiaddr/blob/master/protocols.csv, so that drift from the
+/// upstream table is caught. When re-syncing it, download the file from a specific upstream
+/// commit and record that commit hash here.
+///
+/// Protocols that are not in the upstream table belong in `NOT_UPSTREAM` instead.
+#[test]
+fn protocol_kinds_match_protocols_csv() {
+    const NOT_UPSTREAM: &[ProtocolKind] = &[
+        // Non-standard, see `Protocol::Ws`.
+        ProtocolKind::XParityWs,
+        ProtocolKind::XParityWss,
+        // In the multicodec table only.
+        ProtocolKind::Silverpine,
+    ];
+
+    let csv = include_str!("protocols.csv");
+    let mut seen = Vec::new();
+    for line in csv.lines().skip(1) {
+        let fields: Vec<&str> = line.splitn(4, ',').map(str::trim).collect();
+        let (code, size, name) = (fields[0].parse::<u32>().unwrap(), fields[1], fields[2]);
+        let kind = name
+            .parse::<ProtocolKind>()
+            .unwrap_or_else(|e| panic!("{name} from protocols.csv: {e}"));
+        assert_eq!(kind.code(), code, "code of {name}");
+        assert_eq!(
+            ProtocolKind::try_from(code).unwrap(),
+            kind,
+            "kind of {code}"
+        );
+        let expected = match size {
+            "0" => ValueSize::None,
+            "V" if kind.value_size() == ValueSize::Path => ValueSize::Path,
+            "V" => ValueSize::Variable,
+            bits => ValueSize::Fixed(bits.parse().unwrap()),
+        };
+        assert_eq!(kind.value_size(), expected, "size of {name}");
+        seen.push(kind);
+    }
+
+    for kind in ProtocolKind::ALL {
+        let upstream = !NOT_UPSTREAM.contains(kind);
+        assert_eq!(seen.contains(kind), upstream, "{kind} in protocols.csv");
+    }
+}
+
+#[test]
+fn standard_ws_path() {
+    let standard: Multiaddr = "/ip4/1.2.3.4/tcp/80/ws/http-path/chat%2Froom"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        HEXUPPER.encode(&standard.to_vec()),
+        "0401020304060050DD03E10309636861742F726F6F6D"
+    );
+    assert_eq!(
+        standard.iter().skip(2).collect::<Vec<_>>(),
+        vec![
+            Protocol::Ws("/".into()),
+            Protocol::HttpPath("chat/room".into())
+        ]
+    );
+    #[cfg(feature = "url")]
+    assert_eq!(standard.to_url().unwrap(), "ws://1.2.3.4/chat/room");
+    assert!(standard.validate().is_ok());
+    assert!(patterns::websocket().matches(&standard));
+
+    let legacy: Multiaddr = "/ip4/1.2.3.4/tcp/80/x-parity-ws/%2Fchat%2Froom"
+        .parse()
+        .unwrap();
+    assert!(standard.is_equivalent(&legacy));
+    assert_eq!(legacy.normalize(), standard);
+    assert_eq!(standard.normalize(), standard);
+
+    // A `Ws` with a path is always a single component, in the legacy form.
+    let mut addr = Multiaddr::empty();
+    addr.push(Protocol::Ws("/chat".into()));
+    assert_eq!(addr.iter().count(), 1);
+    assert_eq!(addr.to_string(), "/x-parity-ws/%2Fchat");
+    assert_eq!(addr.pop(), Some(Protocol::Ws("/chat".into())));
+
+    // Either form can be emitted explicitly, and each converts back into the other.
+    let base: Multiaddr = "/ip4/1.2.3.4/tcp/80".parse().unwrap();
+    assert_eq!(
+        base.clone()
+            .with_ws_path("/chat/room", WsPathEncoding::Standard),
+        standard
+    );
+    assert_eq!(
+        base.clone()
+            .with_ws_path("chat/room", WsPathEncoding::Legacy),
+        legacy
+    );
+    assert_eq!(
+        base.clone().with_ws_path("/", WsPathEncoding::Legacy),
+        base.clone().with(Protocol::Ws("/".into()))
+    );
+    assert_eq!(
+        legacy.iter().last().unwrap().kind(),
+        Some(ProtocolKind::XParityWs)
+    );
+    assert_eq!(
+        legacy.to_ws_path_encoding(WsPathEncoding::Standard),
+        standard
+    );
+    assert_eq!(standard.to_ws_path_encoding(WsPathEncoding::Legacy), legacy);
+    assert_eq!(legacy.to_ws_path_encoding(WsPathEncoding::Legacy), legacy);
+    assert_eq!(
+        standard.to_ws_path_encoding(WsPathEncoding::Standard),
+        standard
+    );
+    let unrelated: Multiaddr = "/ip6/::ffff:1.2.3.4/tcp/443/https/x-parity-wss/%2Fa"
+        .parse()
+        .unwrap();
+    let converted = unrelated.to_ws_path_encoding(WsPathEncoding::Standard);
+    assert_eq!(
+        converted.to_string(),
+        "/ip6/::ffff:1.2.3.4/tcp/443/https/wss/http-path/a"
+    );
+    assert_eq!(Multiaddr::try_from(converted.to_vec()).unwrap(), converted);
+    assert_eq!(
+        converted.to_ws_path_encoding(WsPathEncoding::Legacy),
+        unrelated
+    );
+
+    let secure: Multiaddr = "/ip4/1.2.3.4/tcp/443/tls/ws/http-path/chat"
+        .parse()
+        .unwrap();
+    #[cfg(feature = "url")]
+    assert_eq!(secure.to_url().unwrap(), "wss://1.2.3.4/chat");
+    assert!(patterns::websocket_secure().matches(&secure));
+}
+
+#[test]
+fn unix_paths() {
+    let addr: Multiaddr = "/unix/%2Ftmp%2Fnode.sock".parse().unwrap();
+    assert_eq!(
+        addr.iter().collect::<Vec<_>>(),
+        vec![Protocol::Unix("/tmp/node.sock".into())]
+    );
+    assert_eq!(
+        HEXUPPER.encode(&addr.to_vec()),
+        "90030E2F746D702F6E6F64652E736F636B"
+    );
+    assert_eq!(addr.to_string(), "/unix/%2Ftmp%2Fnode.sock");
+
+    // The path is a single segment, so other components may follow it.
+    let peer = "/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC";
+    let with_peer = addr.clone().with(Protocol::P2p(peer_id(&peer[5..])));
+    assert_eq!(
+        with_peer.to_string(),
+        format!("/unix/%2Ftmp%2Fnode.sock{peer}")
+    );
+    assert_eq!(
+        format!("/unix/%2Ftmp%2Fnode.sock{peer}")
+            .parse::<Multiaddr>()
+            .unwrap(),
+        with_peer
+    );
+    assert!(matches!(
+        "/unix/%2Ftmp/node.sock".parse::<Multiaddr>().unwrap_err().inner(),
+        Error::UnknownProtocolString(s) if s == "node.sock"
+    ));
+
+    for (path, s) in [
+        ("node.sock", "/unix/node.sock"),
+        ("run/node.sock", "/unix/run%2Fnode.sock"),
+        (
+            "/run/user/1000/a b.sock",
+            "/unix/%2Frun%2Fuser%2F1000%2Fa b.sock",
+        ),
+        ("/tmp/100%/é.sock", "/unix/%2Ftmp%2F100%25%2Fé.sock"),
+        ("\0abstract", "/unix/\0abstract"),
+        ("/", "/unix/%2F"),
+    ] {
+        let addr = Multiaddr::from(Protocol::Unix(path.into()));
+        assert_eq!(addr.to_string(), s);
+        assert_eq!(s.parse::<Multiaddr>().unwrap(), addr);
+        let with_peer = addr.with(Protocol::P2p(peer_id(&peer[5..])));
+        assert_eq!(
+            with_peer.to_string().parse::<Multiaddr>().unwrap(),
+            with_peer,
+            "{path}"
+        );
+    }
+    assert!("/unix".parse::<Multiaddr>().is_err());
+}
+
+#[test]
+fn unix_conversions() {
+    use std::path::{Path, PathBuf};
+
+    let addr = Multiaddr::try_from(Path::new("/run/node.sock")).unwrap();
+    assert_eq!(addr, "/unix/%2Frun%2Fnode.sock".parse().unwrap());
+    assert_eq!(
+        PathBuf::try_from(&addr).unwrap(),
+        PathBuf::from("/run/node.sock")
+    );
+    assert_eq!(Multiaddr::from_unix_path(""), Err(UnixAddrErr::Unnamed));
+
+    let (path, rest) = addr
+        .clone()
+        .with(Protocol::P2pCircuit)
+        .to_unix_path()
+        .unwrap();
+    assert_eq!(path, Path::new("/run/node.sock"));
+    assert_eq!(rest, Multiaddr::from(Protocol::P2pCircuit));
+    assert_eq!(
+        PathBuf::try_from(&addr.clone().with(Protocol::P2pCircuit)),
+        Err(UnixAddrErr::UnexpectedProtocol {
+            index: 1,
+            tag: "p2p-circuit"
+        })
+    );
+    assert_eq!(
+        "/memory/1".parse::<Multiaddr>().unwrap().to_unix_path(),
+        Err(UnixAddrErr::UnexpectedProtocol {
+            index: 0,
+            tag: "memory"
+        })
+    );
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::net::SocketAddr;
+
+        let socket = SocketAddr::try_from(&addr).unwrap();
+        assert_eq!(socket.as_pathname(), Some(Path::new("/run/node.sock")));
+        assert_eq!(Multiaddr::try_from(&socket).unwrap(), addr);
+
+        let abstract_addr = Multiaddr::from(Protocol::Unix("\0node".into()));
+        assert_eq!(abstract_addr.to_string(), "/unix/\0node");
+        assert_eq!(abstract_addr.to_unix_path(), Err(UnixAddrErr::Abstract));
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::linux::net::SocketAddrExt;
+
+            let socket = abstract_addr.to_unix_socket_addr().unwrap();
+            assert_eq!(socket.as_abstract_name(), Some(&b"node"[..]));
+            assert_eq!(
+                Multiaddr::from_unix_socket_addr(&socket).unwrap(),
+                abstract_addr
+            );
+        }
+    }
+}